@@ -0,0 +1,23 @@
+// Runs the static analysis on Intcode programs, by default on every
+// input that parses as one: `cargo run --example analyze [FILE...]`.
+use advent_of_code_2019::intcode::analysis::analyze;
+
+fn main() {
+    let mut files: Vec<String> = std::env::args().skip(1).collect();
+    if files.is_empty() {
+        let mut inputs: Vec<_> = std::fs::read_dir("input")
+            .unwrap()
+            .map(|e| e.unwrap().path().display().to_string())
+            .collect();
+        inputs.sort();
+        files = inputs;
+    }
+    for file in files {
+        let input = std::fs::read_to_string(&file).unwrap();
+        let program: Result<Vec<i64>, _> = input.trim().split(',').map(|s| s.parse::<i64>()).collect();
+        match program {
+            Ok(program) if program.len() > 1 => print!("{}: {}", file, analyze(&program)),
+            _ => continue,
+        }
+    }
+}
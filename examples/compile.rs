@@ -0,0 +1,28 @@
+// Compiles a program written in the small language of the Intcode
+// compiler: `cargo run --example compile FILE` prints the Intcode
+// program, `cargo run --example compile FILE INPUT...` runs it with
+// the given input and prints the output.
+use advent_of_code_2019::intcode::compiler::compile;
+use advent_of_code_2019::intcode::IntCode;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let file = args.first().expect("usage: compile FILE [INPUT...]");
+    let source = std::fs::read_to_string(file).unwrap();
+    let program = match compile(&source) {
+        Ok(program) => program,
+        Err(e) => {
+            eprintln!("{}: {}", file, e);
+            std::process::exit(1);
+        }
+    };
+    if args.len() == 1 {
+        let cells: Vec<_> = program.iter().map(|v| v.to_string()).collect();
+        println!("{}", cells.join(","));
+        return;
+    }
+    let mut c = IntCode::new(&program);
+    c.input.extend(args[1..].iter().map(|s| s.parse::<i64>().unwrap()));
+    c.run();
+    println!("{:?}", c.output);
+}
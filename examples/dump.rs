@@ -0,0 +1,38 @@
+// Shows the memory of an Intcode program:
+//   `cargo run --example dump FILE` prints a paged dump,
+//   `cargo run --example dump FILE strings` lists the embedded text,
+//   `cargo run --example dump FILE find TEXT` finds a string,
+//   `cargo run --example dump FILE find 1,2,3` finds a sequence.
+use advent_of_code_2019::intcode::dump::{find_sequence, find_string, strings, Dump};
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let file = args.first().expect("usage: dump FILE [strings | find TEXT]");
+    let input = std::fs::read_to_string(file).unwrap();
+    let memory: Vec<i64> = input.trim().split(',').map(|s| s.parse().unwrap()).collect();
+
+    match args.get(1).map(|s| s.as_str()) {
+        None => {
+            for page in Dump::of(&memory).width(10).pages(20) {
+                println!("{}", page);
+            }
+        }
+        Some("strings") => {
+            for (addr, s) in strings(&memory, 4) {
+                println!("{:>6}: {:?}", addr, s);
+            }
+        }
+        Some("find") => {
+            let pattern = args.get(2).expect("nothing to find");
+            let sequence: Result<Vec<i64>, _> = pattern.split(',').map(|s| s.parse()).collect();
+            let found = match sequence {
+                Ok(sequence) => find_sequence(&memory, &sequence),
+                Err(_) => find_string(&memory, pattern),
+            };
+            for addr in found {
+                println!("{}", Dump::of(&memory).range(addr..addr + 10).width(10));
+            }
+        }
+        Some(cmd) => panic!("unknown command {}", cmd),
+    }
+}
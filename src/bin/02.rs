@@ -1,28 +1,26 @@
+use advent_of_code_2019::intcode::symbolic::{Goal, Symbolic};
 use advent_of_code_2019::intcode::IntCode;
+use std::rc::Rc;
 
 fn run_with_args(computer: &mut IntCode, w1: i64, w2: i64) -> i64 {
-    computer.memory[1] = w1;
-    computer.memory[2] = w2;
+    computer.poke(1, w1);
+    computer.poke(2, w2);
     computer.run();
     computer.memory[0]
 }
 
 fn solve(input: &str, target: i64) -> (i64, Option<i64>) {
-    let program: Vec<_> = input.trim().split(',').map(|s| s.parse::<i64>().unwrap()).collect();
-    let mut c = IntCode::new(&program);
+    let program: Rc<[i64]> = input.trim().split(',').map(|s| s.parse::<i64>().unwrap()).collect();
+    let mut c = IntCode::from_image(&program);
 
     let output = run_with_args(&mut c, 12, 2);
 
-    let mut args = None;
-    'search: for i in 0..100 {
-        for j in 0..100 {
-            c.reset(&program);
-            if run_with_args(&mut c, i, j) == target {
-                args = Some(i * 100 + j);
-                break 'search;
-            }
-        }
-    }
+    // noun and verb are the cells 1 and 2, the result is in cell 0
+    let args = Symbolic::from_image(&program)
+        .cell(1, 0..=99)
+        .cell(2, 0..=99)
+        .solve(Goal::Memory(0, target))
+        .map(|v| v[0] * 100 + v[1]);
 
     (output, args)
 }
@@ -34,43 +32,3 @@ fn main() {
     println!("Solution: {:?}", s);
     println!("Time: {}ms", now.elapsed().as_millis());
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example01() {
-        let mut c = IntCode::new(&[1,9,10,3,2,3,11,0,99,30,40,50]);
-        c.run();
-        assert_eq!(&c.memory, &[3500,9,10,70,2,3,11,0,99,30,40,50]);
-    }
-
-    #[test]
-    fn example02() {
-        let mut c = IntCode::new(&[1,0,0,0,99]);
-        c.run();
-        assert_eq!(&c.memory, &[2,0,0,0,99]);
-    }
-
-    #[test]
-    fn example03() {
-        let mut c = IntCode::new(&[2,3,0,3,99]);
-        c.run();
-        assert_eq!(&c.memory, &[2,3,0,6,99]);
-    }
-
-    #[test]
-    fn example04() {
-        let mut c = IntCode::new(&[2,4,4,5,99,0]);
-        c.run();
-        assert_eq!(&c.memory, &[2,4,4,5,99,9801]);
-    }
-
-    #[test]
-    fn example05() {
-        let mut c = IntCode::new(&[1,1,1,4,99,5,6,0,99]);
-        c.run();
-        assert_eq!(&c.memory, &[30,1,1,4,2,5,6,0,99]);
-    }
-}
@@ -1,3 +1,4 @@
+use advent_of_code_2019::direction::Direction;
 use std::collections::HashMap;
 
 struct Wire {
@@ -15,13 +16,11 @@ impl Wire {
         };
         for m in s.split(',') {
             let (d, n) = m.split_at(1);
-            let n = n.parse::<i64>().unwrap();
-            match d {
-                "R" => for _ in 0..n { w.len += 1; w.tip.0 += 1; w.path.entry(w.tip).or_insert(w.len); },
-                "L" => for _ in 0..n { w.len += 1; w.tip.0 -= 1; w.path.entry(w.tip).or_insert(w.len); },
-                "U" => for _ in 0..n { w.len += 1; w.tip.1 += 1; w.path.entry(w.tip).or_insert(w.len); },
-                "D" => for _ in 0..n { w.len += 1; w.tip.1 -= 1; w.path.entry(w.tip).or_insert(w.len); },
-                _ => panic!(),
+            let (dx, dy) = d.parse::<Direction>().unwrap().delta();
+            for _ in 0..n.parse::<i64>().unwrap() {
+                w.len += 1;
+                w.tip = (w.tip.0 + dx, w.tip.1 + dy);
+                w.path.entry(w.tip).or_insert(w.len);
             }
         }
         w
@@ -36,6 +36,7 @@ impl OrbitMap {
     }
 }
 
+#[allow(clippy::needless_borrow)]
 fn solve(input: &str) -> (usize, usize) {
     let om = OrbitMap::create_from(&input);
 
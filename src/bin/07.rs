@@ -1,3 +1,4 @@
+use advent_of_code_2019::intcode::scheduler::{Outcome, Scheduler};
 use advent_of_code_2019::intcode::IntCode;
 
 trait Permute {
@@ -20,36 +21,32 @@ impl<T> Permute for [T] {
 }
 
 fn run_amplifiers(program: &[i64], config: &[i64; 5]) -> i64 {
-    let mut amp = [
-        IntCode::new(program),
-        IntCode::new(program),
-        IntCode::new(program),
-        IntCode::new(program),
-        IntCode::new(program),
-    ];
-
-    for i in 0..5 {
-        amp[i].input.push_back(config[i]);
-        amp[i].run();
-    }
-
-    amp[0].input.push_back(0);
-    loop {
-        amp[0].run();
-        amp[1].input.push_back(amp[0].output.pop().unwrap());
-        amp[1].run();
-        amp[2].input.push_back(amp[1].output.pop().unwrap());
-        amp[2].run();
-        amp[3].input.push_back(amp[2].output.pop().unwrap());
-        amp[3].run();
-        amp[4].input.push_back(amp[3].output.pop().unwrap());
-        amp[4].run();
-        if amp[4].is_halted() {
-            break;
-        }
-        amp[0].input.push_back(amp[4].output.pop().unwrap());
-    }
-    amp[4].output.pop().unwrap()
+    let amps = config
+        .iter()
+        .map(|phase| {
+            let mut c = IntCode::new(program);
+            c.input.push_back(*phase);
+            c
+        })
+        .collect();
+    let mut amps = Scheduler::new(amps);
+
+    // each amplifier feeds the next, the last one feeds the first
+    amps.send(0, &[0]);
+    let mut signal = None;
+    let outcome = amps.run(|from, c| {
+        c.output
+            .drain(..)
+            .map(|v| {
+                if from == 4 {
+                    signal = Some(v);
+                }
+                ((from + 1) % 5, v)
+            })
+            .collect()
+    });
+    assert_eq!(outcome, Outcome::Halted);
+    signal.unwrap()
 }
 
 fn max_amplifiers(program: &[i64], range: &[i64; 5]) -> ([i64; 5], i64) {
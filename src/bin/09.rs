@@ -23,32 +23,3 @@ fn main() {
     println!("Solution: {:?}", s);
     println!("Time: {}ms", now.elapsed().as_millis());
 }
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn example01() {
-        let mut c = IntCode::new(&[109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99]);
-        c.run();
-        assert_eq!(
-            c.output,
-            vec![109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99]
-        );
-    }
-
-    #[test]
-    fn example02() {
-        let mut c = IntCode::new(&[1102, 34915192, 34915192, 7, 4, 7, 99, 0]);
-        c.run();
-        assert_eq!(c.output, vec![1219070632396864]);
-    }
-
-    #[test]
-    fn example03() {
-        let mut c = IntCode::new(&[104, 1125899906842624, 99]);
-        c.run();
-        assert_eq!(c.output, vec![1125899906842624]);
-    }
-}
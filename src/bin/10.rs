@@ -1,96 +1,22 @@
-use advent_of_code_2019::grid::{Grid, Location};
-use std::cmp::Ordering;
-use std::collections::HashSet;
+use advent_of_code_2019::grid::{angular_sort, Grid, Location};
+use std::collections::HashMap;
 
-fn gcd(mut m: i64, mut n: i64) -> i64 {
-    while m != 0 {
-        let old_m = m;
-        m = n % m;
-        n = old_m;
-    }
-    n.abs()
-}
-
-// This structure encodes an "amount of rotation" as three integers
-// (n, a, b), where n is a number of half rotations, and a, b with
-// a >= 0 and a²+b² > 0 encode an additional rotation by alpha:
-//   (a, b) / (a²+b²) = (sin(alpha), cos(alpha))
-#[derive(Debug, PartialEq, Eq)]
-struct IntegerRotation {
-    n: u64,
-    a: i64,
-    b: i64,
-}
-
-impl PartialOrd for IntegerRotation {
-    fn partial_cmp(&self, other: &IntegerRotation) -> Option<Ordering> {
-        Some(u64::cmp(&self.n, &other.n).then(i64::cmp(&(other.b * self.a), &(self.b * other.a))))
-    }
-}
-
-struct AsteroidMap(HashSet<Location>);
+struct AsteroidMap(Grid<bool>);
 
 impl AsteroidMap {
     fn create_from(s: &str) -> AsteroidMap {
-        let mut asteroids = HashSet::new();
-        let g = Grid::create_from(s);
-        for (l, c) in g.symbols.iter() {
-            if c == &'#' {
-                asteroids.insert(*l);
-            }
-        }
-        AsteroidMap(asteroids)
+        AsteroidMap(Grid::create_with(s, |c| Some(c == '#')))
     }
 
-    fn asteroids_on_path(&self, from: &Location, to: &Location) -> u64 {
-        let mut res = 0;
-        let x_diff = to.x - from.x;
-        let y_diff = to.y - from.y;
-        let gcd = gcd(x_diff, y_diff);
-
-        for i in 1..gcd {
-            let inside_location = Location {
-                x: from.x + x_diff / gcd * i,
-                y: from.y + y_diff / gcd * i,
-            };
-            if self.0.contains(&inside_location) {
-                res += 1;
-            }
-        }
-        res
-    }
-
-    fn direct_line(&self, from: &Location, to: &Location) -> bool {
-        self.asteroids_on_path(from, to) == 0
-    }
-
-    fn angle_from_vertical(&self, from: &Location, to: &Location) -> IntegerRotation {
-        let x_diff = to.x - from.x;
-        let y_diff = -(to.y - from.y);
-
-        if x_diff > 0 || (x_diff == 0 && y_diff > 0) {
-            IntegerRotation {
-                n: self.asteroids_on_path(from, to) * 2,
-                a: x_diff,
-                b: y_diff,
-            }
-        } else {
-            IntegerRotation {
-                n: self.asteroids_on_path(from, to) * 2 + 1,
-                a: -x_diff,
-                b: -y_diff,
-            }
-        }
+    fn asteroids(&self) -> impl Iterator<Item = &Location> + '_ {
+        self.0.symbols.iter().filter(|(_, &asteroid)| asteroid).map(|(l, _)| l)
     }
 }
 
 fn max_visibility(m: &AsteroidMap) -> (&Location, usize) {
     let mut best_loc = (&Location { x: 0, y: 0 }, 0);
-    for a in m.0.iter() {
-        let visible_from_a =
-            m.0.iter()
-                .filter(|b| (*b != a) && m.direct_line(a, b))
-                .count();
+    for a in m.asteroids() {
+        let visible_from_a = m.0.visible_from(a, |&asteroid| asteroid).len();
 
         if visible_from_a > best_loc.1 {
             best_loc = (a, visible_from_a);
@@ -99,14 +25,24 @@ fn max_visibility(m: &AsteroidMap) -> (&Location, usize) {
     best_loc
 }
 
+// The laser turns clockwise from up, vaporizing the nearest asteroid in
+// each direction at each turn.
 fn nth_target<'a>(m: &'a AsteroidMap, station: &Location, n: usize) -> &'a Location {
-    let mut angles: Vec<_> =
-        m.0.iter()
-            .filter(|b| (b != &station))
-            .map(|b| (b, m.angle_from_vertical(station, b)))
-            .collect();
-    angles.sort_by(|(_, ang1), (_, ang2)| ang1.partial_cmp(ang2).unwrap());
-    angles.get(n - 1).unwrap().0
+    let mut targets: Vec<_> = m.asteroids().filter(|&b| b != station).collect();
+    angular_sort(station, &mut targets);
+
+    // the turn when each asteroid is hit
+    let mut hidden = HashMap::new();
+    let mut turns: Vec<_> = targets
+        .into_iter()
+        .map(|b| {
+            let turn = hidden.entry(station.step_towards(b)).or_insert(0);
+            *turn += 1;
+            (*turn, b)
+        })
+        .collect();
+    turns.sort_by(|(t1, b1), (t2, b2)| t1.cmp(t2).then_with(|| station.clockwise_cmp(b1, b2)));
+    turns[n - 1].1
 }
 
 fn solve(input: &str, n: usize) -> (usize, i64) {
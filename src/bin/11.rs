@@ -1,43 +1,58 @@
-use advent_of_code_2019::grid::{Direction::*, Grid, Location};
+use advent_of_code_2019::grid::{Direction::{self, *}, Grid, Location};
+use advent_of_code_2019::intcode::controller::{drive, Controller};
 use advent_of_code_2019::intcode::IntCode;
+use advent_of_code_2019::log;
+use advent_of_code_2019::netpbm::{Format, Netpbm, BLACK, WHITE};
+use advent_of_code_2019::ocr;
 
-fn paint(program: &[i64], g: &mut Grid) {
-    let mut c = IntCode::new(program);
-    let mut pos = Location { x: 0, y: 0 };
-    let mut dir = Up;
+// The painting robot, driven by the program: it reads the color under
+// the robot, then gets the color to paint and the direction to turn.
+struct Robot {
+    grid: Grid,
+    pos: Location,
+    dir: Direction,
+}
+
+impl Controller for Robot {
+    type Frame = [i64; 2];
 
-    while !c.is_halted() {
-        match g.get(&pos) {
-            Some('\u{2588}') => c.input.push_back(1),
-            _ => c.input.push_back(0),
+    fn next_input(&mut self) -> Option<i64> {
+        match self.grid.get(&self.pos) {
+            Some('\u{2588}') => Some(1),
+            _ => Some(0),
         }
-        c.run();
-        let turn = c.output.pop().unwrap();
-        let color = c.output.pop().unwrap();
+    }
+
+    fn on_output(&mut self, [color, turn]: [i64; 2]) {
         match color {
-            0 => { g.insert(pos, ' '); },
-            1 => { g.insert(pos, '\u{2588}'); },
+            0 => { self.grid.insert(self.pos, ' '); },
+            1 => { self.grid.insert(self.pos, '\u{2588}'); },
             _ => { panic!(); },
         }
         match turn {
-            0 => { dir = dir.left(); pos = pos.go(dir); },
-            1 => { dir = dir.right(); pos = pos.go(dir); },
+            0 => { self.dir = self.dir.left(); self.pos = self.pos.go(self.dir); },
+            1 => { self.dir = self.dir.right(); self.pos = self.pos.go(self.dir); },
             _ => panic!(),
         }
     }
 }
 
+fn paint(program: &[i64], grid: Grid) -> Grid {
+    let mut robot = Robot { grid, pos: Location { x: 0, y: 0 }, dir: Up };
+    drive(&mut IntCode::new(program), &mut robot);
+    robot.grid
+}
+
 fn solve(input: &str) -> (usize, String) {
     let program: Vec<_> = input.trim().split(',').map(|s| s.parse::<i64>().unwrap()).collect();
-    let mut g = Grid::new();
-
-    paint(&program, &mut g);
-    let painted = g.symbols.len();
+    let painted = paint(&program, Grid::new()).symbols.len();
 
     let mut g = Grid::new();
     g.insert(Location { x: 0, y: 0 }, '\u{2588}');
-    paint(&program, &mut g);
-    let plate = g.to_string();
+    let hull = paint(&program, g);
+    log::export("11.pbm", &Netpbm::new(&hull, Format::Pbm).color('\u{2588}', WHITE).with_background(BLACK).with_scale(4));
+    log!("{}", hull);
+    let plate = ocr::read(&hull, |&c| c == '\u{2588}').unwrap_or_else(|e| panic!("{}", e));
 
     (painted, plate)
 }
@@ -46,6 +61,24 @@ fn main() {
     let input = std::fs::read_to_string("input/11.txt").unwrap();
     let now = std::time::Instant::now();
     let s = solve(&input);
-    println!("Solution: {}\n{}", s.0, s.1);
+    println!("Solution: {:?}", s);
     println!("Time: {}ms", now.elapsed().as_millis());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example01() {
+        let mut robot = Robot { grid: Grid::new(), pos: Location { x: 0, y: 0 }, dir: Up };
+        let mut seen = Vec::new();
+        for frame in [[1, 0], [0, 0], [1, 0], [1, 0], [0, 1], [1, 0], [1, 0]].iter() {
+            seen.push(robot.next_input().unwrap());
+            robot.on_output(*frame);
+        }
+        assert_eq!(seen, vec![0, 0, 0, 0, 1, 0, 0]);
+        assert_eq!(robot.grid.symbols.len(), 6);
+        assert_eq!(robot.pos, Location { x: 0, y: -1 });
+    }
+}
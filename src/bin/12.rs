@@ -1,4 +1,5 @@
-use std::cmp::Ordering;
+use advent_of_code_2019::grid::Point3;
+use advent_of_code_2019::log;
 
 fn gcd(mut m: usize, mut n: usize) -> usize {
     while m != 0 {
@@ -15,8 +16,8 @@ fn lcm(m: usize, n: usize) -> usize {
 
 #[derive(Debug, Clone)]
 struct Moon {
-    pos: [i64; 3],
-    vel: [i64; 3],
+    pos: Point3,
+    vel: Point3,
 }
 
 impl Moon {
@@ -25,37 +26,31 @@ impl Moon {
             .split(", ")
             .map(|e| e[2..].parse::<i64>().unwrap());
         Moon {
-            pos: [
+            pos: Point3::new(
                 values.next().unwrap(),
                 values.next().unwrap(),
                 values.next().unwrap(),
-            ],
-            vel: [0, 0, 0],
+            ),
+            vel: Point3::ORIGIN,
         }
     }
 
     fn energy(&self) -> i64 {
-        (self.pos[0].abs() + self.pos[1].abs() + self.pos[2].abs())
-            * (self.vel[0].abs() + self.vel[1].abs() + self.vel[2].abs())
+        self.pos.norm1() * self.vel.norm1()
     }
 }
 
+#[allow(clippy::needless_range_loop)]
 fn step(moons: &mut [Moon]) {
     for i in 0..moons.len() {
         for j in i + 1..moons.len() {
-            for d in 0..3 {
-                match moons[i].pos[d].cmp(&moons[j].pos[d]) {
-                    Ordering::Less => { moons[i].vel[d] += 1; moons[j].vel[d] -= 1; },
-                    Ordering::Greater => { moons[i].vel[d] -= 1; moons[j].vel[d] += 1; },
-                    Ordering::Equal => {},
-                }
-            }
+            let gravity = (moons[j].pos - moons[i].pos).signum();
+            moons[i].vel += gravity;
+            moons[j].vel -= gravity;
         }
     }
     for i in 0..moons.len() {
-        for d in 0..3 {
-            moons[i].pos[d] += moons[i].vel[d];
-        }
+        moons[i].pos += moons[i].vel;
     }
 }
 
@@ -84,7 +79,7 @@ fn simulate(moons: &mut [Moon], steps: usize) -> (i64, usize) {
 
         if n == steps {
             target_energy = moons.iter().map(Moon::energy).sum();
-            println!("Energy after {} steps: {}", n, target_energy);
+            log!("Energy after {} steps: {}", n, target_energy);
         }
     }
 
@@ -1,75 +1,97 @@
-use advent_of_code_2019::grid::{Grid, Location};
+use advent_of_code_2019::grid::{Glyph, Grid, Location};
+use advent_of_code_2019::intcode::controller::{drive, Controller};
 use advent_of_code_2019::intcode::IntCode;
 use std::cmp::Ordering;
 
-struct Game {
-    computer: IntCode,
-    screen: Grid,
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tile {
+    Wall,
+    Block,
+    Paddle,
+    Ball,
+}
+
+impl Glyph for Tile {
+    fn glyph(&self) -> char {
+        match self {
+            Tile::Wall => '#',
+            Tile::Block => '\u{2588}',
+            Tile::Paddle => '_',
+            Tile::Ball => 'o',
+        }
+    }
+
+    fn from_glyph(c: char) -> Option<Tile> {
+        [Tile::Wall, Tile::Block, Tile::Paddle, Tile::Ball].iter().copied().find(|t| t.glyph() == c)
+    }
+}
+
+// The arcade cabinet: draws the tiles sent by the program, and moves
+// the joystick to keep the paddle under the ball.
+struct Arcade {
+    screen: Grid<Tile>,
     paddle: (i64, i64),
     ball: (i64, i64),
     score: i64,
 }
 
-impl Game {
-    fn new(program: &[i64]) -> Game {
-        Game {
-            computer: IntCode::new(program),
+impl Arcade {
+    fn new() -> Arcade {
+        Arcade {
             screen: Grid::new(),
             paddle: (0, 0),
             ball: (0, 0),
             score: 0,
         }
     }
+}
 
-    fn set_move(&mut self, m: i64) {
-        self.computer.input.push_back(m);
-    }
+impl Controller for Arcade {
+    type Frame = [i64; 3];
 
-    fn run(&mut self) {
-        self.computer.run();
-        for cell in self.computer.output.chunks_exact(3) {
-            match *cell {
-                [-1, 0, v] => { self.score = v; },
-                [x, y, 0] => { self.screen.remove(&Location{ x, y }); },
-                [x, y, 1] => { self.screen.insert(Location{ x, y }, '#'); },
-                [x, y, 2] => { self.screen.insert(Location{ x, y }, '\u{2588}'); },
-                [x, y, 3] => {
-                    self.screen.insert(Location { x, y }, '_');
-                    self.paddle = (x, y);
-                }
-                [x, y, 4] => {
-                    self.screen.insert(Location { x, y }, 'o');
-                    self.ball = (x, y);
-                }
-                _ => panic!(),
-            }
-        }
-        self.computer.output.clear();
+    fn next_input(&mut self) -> Option<i64> {
         // uncomment to show the game
         //println!("{}", self.screen);
         //std::thread::sleep(std::time::Duration::from_millis(10));
+        match i64::cmp(&self.paddle.0, &self.ball.0) {
+            Ordering::Less => Some(1),
+            Ordering::Equal => Some(0),
+            Ordering::Greater => Some(-1),
+        }
+    }
+
+    fn on_output(&mut self, cell: [i64; 3]) {
+        match cell {
+            [-1, 0, v] => { self.score = v; },
+            [x, y, 0] => { self.screen.remove(&Location{ x, y }); },
+            [x, y, 1] => { self.screen.insert(Location{ x, y }, Tile::Wall); },
+            [x, y, 2] => { self.screen.insert(Location{ x, y }, Tile::Block); },
+            [x, y, 3] => {
+                self.screen.insert(Location { x, y }, Tile::Paddle);
+                self.paddle = (x, y);
+            }
+            [x, y, 4] => {
+                self.screen.insert(Location { x, y }, Tile::Ball);
+                self.ball = (x, y);
+            }
+            _ => panic!(),
+        }
     }
 }
 
 fn solve(input: &str) -> (usize, i64) {
     let program: Vec<_> = input.trim().split(',').map(|s| s.parse::<i64>().unwrap()).collect();
-    let mut g = Game::new(&program);
-    g.run();
+    let mut arcade = Arcade::new();
+    drive(&mut IntCode::new(&program), &mut arcade);
 
-    let blocks = g.screen.symbols.values().filter(|&x| x == &'\u{2588}').count();
-    
-    let mut g = Game::new(&program);
-    g.computer.memory[0] = 2;
-    while !g.computer.is_halted() {
-        g.run();
-        match i64::cmp(&g.paddle.0, &g.ball.0) {
-            Ordering::Less => g.set_move(1),
-            Ordering::Equal => g.set_move(0),
-            Ordering::Greater => g.set_move(-1),
-        }
-    }
+    let blocks = arcade.screen.symbols.values().filter(|&t| t == &Tile::Block).count();
 
-    (blocks, g.score)
+    let mut computer = IntCode::new(&program);
+    computer.memory[0] = 2;
+    let mut arcade = Arcade::new();
+    drive(&mut computer, &mut arcade);
+
+    (blocks, arcade.score)
 }
 
 fn main() {
@@ -79,3 +101,24 @@ fn main() {
     println!("Solution: {:?}", s);
     println!("Time: {}ms", now.elapsed().as_millis());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example01() {
+        let mut arcade = Arcade::new();
+        for cell in [[1, 2, 3], [6, 5, 4], [2, 2, 2], [3, 2, 2], [2, 2, 0], [-1, 0, 12]].iter() {
+            arcade.on_output(*cell);
+        }
+        assert_eq!(arcade.screen.symbols.values().filter(|&t| t == &Tile::Block).count(), 1);
+        assert_eq!(arcade.screen.to_string(), "_ \u{2588}   \n      \n      \n     o\n");
+        assert_eq!(arcade.score, 12);
+        assert_eq!(arcade.next_input(), Some(1));
+        arcade.on_output([6, 20, 3]);
+        assert_eq!(arcade.next_input(), Some(0));
+        arcade.on_output([5, 6, 4]);
+        assert_eq!(arcade.next_input(), Some(-1));
+    }
+}
@@ -1,116 +1,105 @@
-use advent_of_code_2019::graph::{Backtracking, UnweightedGraph};
-use advent_of_code_2019::grid::{Direction::{self, *}, Grid, Location};
-use advent_of_code_2019::intcode::IntCode;
-
-fn direction_code(direction: Direction) -> i64 {
-    match direction {
-        Up => 1,
-        Down => 2,
-        Left => 3,
-        Right => 4,
+use advent_of_code_2019::graph::UnweightedGraph;
+use advent_of_code_2019::grid::{Direction::{self, *}, Glyph, Grid, Location, WalkableGrid};
+use advent_of_code_2019::intcode::controller::{drive, Controller};
+use advent_of_code_2019::intcode::{IntCode, Machine};
+use advent_of_code_2019::log;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Tile {
+    Wall,
+    Open,
+    Oxygen,
+}
+
+impl Glyph for Tile {
+    fn glyph(&self) -> char {
+        match self {
+            Tile::Wall => '#',
+            Tile::Open => '.',
+            Tile::Oxygen => 'O',
+        }
+    }
+
+    fn from_glyph(c: char) -> Option<Tile> {
+        match c {
+            '#' => Some(Tile::Wall),
+            '.' => Some(Tile::Open),
+            'O' => Some(Tile::Oxygen),
+            _ => None,
+        }
     }
 }
 
+// The droid explores the whole area depth first: it moves to a cell not
+// explored yet next to it if there is one, and otherwise goes back the
+// way it came.
 struct ExplorerRobot {
-    computer: IntCode,
-    grid: Grid,
+    grid: Grid<Tile>,
     location: Location,
+    path: Vec<Direction>,              // the moves from the start
+    moving: Option<(Direction, bool)>, // the move sent, and whether it goes back
 }
 
 impl ExplorerRobot {
-    fn new(program: &[i64]) -> ExplorerRobot {
-        let computer = IntCode::new(program);
+    fn new() -> ExplorerRobot {
         let mut grid = Grid::new();
         let location = Location { x: 0, y: 0 };
-        grid.insert(location, '.');
+        grid.insert(location, Tile::Open);
         ExplorerRobot {
-            computer,
             grid,
             location,
+            path: Vec::new(),
+            moving: None,
         }
     }
 }
 
-impl Backtracking for ExplorerRobot {
-    type Action = Direction;
+impl Controller for ExplorerRobot {
+    type Frame = [i64; 1];
 
-    fn list_actions(&self) -> Vec<Self::Action> {
-        let mut v = Vec::new();
-        for d in &[Up, Down, Left, Right] {
-            if self.grid.get(&self.location.go(*d)).is_none() {
-                v.push(*d);
-            }
-        }
-        v
+    fn next_input(&mut self) -> Option<i64> {
+        let unexplored = [Up, Down, Left, Right].iter().find(|d| self.grid.get(&self.location.go(**d)).is_none());
+        self.moving = match unexplored {
+            Some(d) => Some((*d, false)),
+            None => Some((self.path.pop()?.reverse(), true)),
+        };
+        self.moving.map(|(d, _)| d.code())
     }
 
-    fn try_action(&mut self, action: &Self::Action) -> bool {
-        if self.grid.get(&self.location.go(*action)).is_some() {
-            // going to a cell already explored
-            return false;
+    fn on_output(&mut self, [status]: [i64; 1]) {
+        let (d, back) = self.moving.take().unwrap();
+        let next = self.location.go(d);
+        if back {
+            self.location = next;
+            return;
         }
-
-        self.computer.input.push_back(direction_code(*action));
-        self.computer.run();
-        match self.computer.output.pop().unwrap() {
-            0 => {
-                self.grid.insert(self.location.go(*action), '#');
-                false
-            }
-            1 => {
-                self.grid.insert(self.location.go(*action), '.');
-                self.location = self.location.go(*action);
-                true
-            }
-            2 => {
-                self.grid.insert(self.location.go(*action), 'O');
-                self.location = self.location.go(*action);
-                true
-            }
+        let tile = match status {
+            0 => Tile::Wall,
+            1 => Tile::Open,
+            2 => Tile::Oxygen,
             _ => panic!(),
+        };
+        self.grid.insert(next, tile);
+        if tile != Tile::Wall {
+            self.location = next;
+            self.path.push(d);
         }
     }
-
-    fn backtrack(&mut self, action: &Self::Action) {
-        self.computer
-            .input
-            .push_back(direction_code(action.reverse()));
-        self.computer.run();
-        self.location = self.location.go(action.reverse());
-    }
-
-    fn is_solution(&self) -> bool {
-        false
-    }
-}
-
-// This object represents a Grid where dots are free space
-// that can be walked on, forming a graph.
-struct WalkableGrid(Grid);
-
-impl UnweightedGraph<Location> for WalkableGrid {
-    fn edges(&self, node: &Location) -> Vec<Location> {
-        let mut v = Vec::new();
-
-        for d in &[Up, Down, Left, Right] {
-            let next_node = node.go(*d);
-            if self.0.get(&next_node) == Some(&'.') {
-                v.push(next_node);
-            }
-        }
-        v
-    }
 }
 
 fn solve(input: &str) -> (usize, usize) {
     let program: Vec<_> = input.trim().split(',').map(|s| s.parse::<i64>().unwrap()).collect();
-    let mut robot = ExplorerRobot::new(&program);
+    explore(IntCode::new(&program))
+}
+
+fn explore<M: Machine>(mut computer: M) -> (usize, usize) {
+    let mut robot = ExplorerRobot::new();
 
-    robot.explore();
-    println!("{}", robot.grid);
+    drive(&mut computer, &mut robot);
+    log!("{}", robot.grid);
 
-    let oxigen_location = *robot.grid.find('O').unwrap();
-    let oxigen_distance = WalkableGrid(robot.grid).shortest_paths(oxigen_location, &[]);
+    let oxigen_location = *robot.grid.find(Tile::Oxygen).unwrap();
+    let oxigen_distance = WalkableGrid::new(&robot.grid, |&t| t == Tile::Open).shortest_paths(oxigen_location, &[]);
 
     (
         oxigen_distance[&Location { x: 0, y: 0 }],
@@ -125,3 +114,47 @@ fn main() {
     println!("Solution: {:?}", s);
     println!("Time: {}ms", now.elapsed().as_millis());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2019::intcode::mock::Mock;
+
+    // A repair droid moving in a known maze, starting on `D`.
+    fn maze(map: &str) -> Mock<impl FnMut(i64) -> Option<Vec<i64>>> {
+        let grid = Grid::create_with(map, |c| Tile::from_glyph(if c == 'D' { '.' } else { c }));
+        let mut droid = *Grid::<char>::create_from(map).find('D').unwrap();
+        Mock::new(move |code| {
+            let next = droid.go(Direction::from_code(code).unwrap());
+            Some(vec![match grid.get(&next) {
+                Some(Tile::Wall) | None => 0,
+                Some(Tile::Oxygen) => { droid = next; 2 },
+                _ => { droid = next; 1 },
+            }])
+        })
+    }
+
+    #[test]
+    fn example01() {
+        let map = [
+            " ##   ",
+            "#..## ",
+            "#.#..#",
+            "#.O.# ",
+            " #D#  ",
+        ];
+        assert_eq!(explore(maze(&map.join("\n"))).0, 1);
+    }
+
+    #[test]
+    fn example02() {
+        let map = [
+            " ##   ",
+            "#..## ",
+            "#D#..#",
+            "#.O.# ",
+            " ###  ",
+        ];
+        assert_eq!(explore(maze(&map.join("\n"))), (2, 4));
+    }
+}
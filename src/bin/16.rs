@@ -36,6 +36,7 @@
 
 // This function applies a number of FFT phases to the tail of the input
 // vector (from offset to the end of the vector).
+#[allow(clippy::needless_range_loop)]
 fn fft(v: &mut [i64], phases: usize, offset: usize) {
     let n = v.len();
 
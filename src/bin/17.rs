@@ -1,18 +1,12 @@
 use advent_of_code_2019::graph::Backtracking;
-use advent_of_code_2019::grid::{Direction::*, Grid};
+use advent_of_code_2019::grid::{Direction, Grid};
 use advent_of_code_2019::intcode::IntCode;
+use advent_of_code_2019::log;
 
 fn alignment_parameters(area: &Grid) -> i64 {
-    area.symbols
-        .iter()
-        .filter(|(location, value)| {
-            **value == '#'
-                && area.get(&location.go(Up)) == Some(&'#')
-                && area.get(&location.go(Down)) == Some(&'#')
-                && area.get(&location.go(Left)) == Some(&'#')
-                && area.get(&location.go(Right)) == Some(&'#')
-        })
-        .map(|(location, _)| location.x * location.y)
+    area.cells_matching(|&c| c == '#')
+        .filter(|location| location.neighbors4().iter().all(|n| area.get(n) == Some(&'#')))
+        .map(|location| location.x * location.y)
         .sum()
 }
 
@@ -111,10 +105,7 @@ fn plan_path(area: &Grid) -> Vec<String> {
         .symbols
         .iter()
         .find_map(|(location, value)| match value {
-            '^' => Some((*location, Up)),
-            'v' => Some((*location, Down)),
-            '<' => Some((*location, Left)),
-            '>' => Some((*location, Right)),
+            '^' | 'v' | '<' | '>' => Some((*location, value.to_string().parse::<Direction>().unwrap())),
             _ => None,
         })
         .unwrap();
@@ -157,8 +148,7 @@ fn solve(input: &str) -> (i64, i64) {
 
     // plan the path of the robot
     let path = plan_path(&area);
-    println!("Planned path for the robot: {:?}", path);
-    println!();
+    log!("Planned path for the robot: {:?}", path);
 
     // compress the path
     let mut p = Compression::new(&path, 5);
@@ -1,5 +1,6 @@
 use advent_of_code_2019::graph::{Graph, UnweightedGraph};
-use advent_of_code_2019::grid::{Direction::*, Grid, Location};
+use advent_of_code_2019::grid::{Direction::*, Grid, WalkableGrid};
+use advent_of_code_2019::log;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 
@@ -27,34 +28,6 @@ fn key_to_bit(key: char) -> u32 {
     }
 }
 
-// Treat the map as an unweighted graph where the nodes are the
-// non-wall elements.
-struct WalkableGrid<'a> {
-    grid: &'a Grid,
-    start: &'a Location,
-}
-
-impl<'a> UnweightedGraph<Location> for WalkableGrid<'a> {
-    fn edges(&self, node: &Location) -> Vec<Location> {
-        let mut v = Vec::new();
-
-        let c = self.grid.get(node).unwrap();
-        if node != self.start && c != &'.' {
-            // found a new item, stop here
-            return v;
-        }
-
-        for d in &[Up, Down, Left, Right] {
-            let next = node.go(*d);
-            match self.grid.get(&next) {
-                None | Some('#') => {},
-                _ => { v.push(next); },
-            }
-        }
-        v
-    }
-}
-
 // Reduce the graph to the locations with keys/doors, and represent it
 // as an adjacency matrix.
 #[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -72,8 +45,9 @@ fn adjacency_matrix(grid: &Grid) -> AdjacencyMatrix {
     // Build the adjacency matrix
     let mut matrix = BTreeMap::new();
     for (i1, l1) in item_locations.iter() {
-        let graph = WalkableGrid { grid, start: l1 };
-        let dist = graph.shortest_paths(*l1, &[]);
+        // walk on the non-wall cells, stopping at the other items
+        let graph = WalkableGrid::new(grid, |&c| c != '#').with_stop(|l, &c| l != *l1 && c != '.');
+        let dist = UnweightedGraph::shortest_paths(&graph, *l1, &[]);
         for (i2, l2) in item_locations.iter() {
             if i1 != i2 {
                 if let Some(d) = dist.get(l2) {
@@ -126,6 +100,7 @@ impl<'a> Graph<char> for AdjacencyMatrixWithKeys<'a> {
 // the missing keys. Adjacent nodes are nodes where the position moves
 // to a reachable key that was previously missing, and the key is recorded
 // as not missing.
+#[allow(clippy::extra_unused_lifetimes, clippy::needless_borrow)]
 impl<'a> Graph<Node> for AdjacencyMatrix {
     fn edges(&self, node: &Node) -> Vec<(Node, usize)> {
         let mut v = Vec::new();
@@ -158,6 +133,7 @@ struct Node4 {
 // and the missing keys. Adjacent nodes are nodes where the position of
 // one of the robots moves to a reachable key that was previously missing,
 // and the key is recorded as not missing.
+#[allow(clippy::extra_unused_lifetimes, clippy::needless_borrow)]
 impl<'a> Graph<Node4> for AdjacencyMatrix {
     fn edges(&self, node: &Node4) -> Vec<(Node4, usize)> {
         let mut v = Vec::new();
@@ -182,11 +158,12 @@ impl<'a> Graph<Node4> for AdjacencyMatrix {
     }
 }
 
+#[allow(clippy::needless_borrow)]
 fn min_distance(area: &Grid) -> usize {
-    println!("{}", area);
+    log!("{}", area);
     let matrix = adjacency_matrix(&area);
     let keys: Vec<_> = matrix.0.keys().filter(|c| c.is_ascii_lowercase()).copied().collect();
-    println!("Keys to collect: {:?}", keys);
+    log!("Keys to collect: {:?}", keys);
 
     let start = Node {
         position: '@',
@@ -206,11 +183,12 @@ fn min_distance(area: &Grid) -> usize {
         .unwrap()
 }
 
+#[allow(clippy::needless_borrow)]
 fn min_distance4(area: &Grid) -> usize {
-    println!("{}", area);
+    log!("{}", area);
     let matrix = adjacency_matrix(&area);
     let keys: Vec<_> = matrix.0.keys().filter(|c| c.is_ascii_lowercase()).copied().collect();
-    println!("Keys to collect: {:?}", keys);
+    log!("Keys to collect: {:?}", keys);
 
     let start = Node4 {
         position: ['1', '2', '3', '4'],
@@ -238,10 +216,9 @@ fn solve(input: &str) -> (usize, usize) {
     // change the map, splitting it into four
     let entrance = *area.find('@').unwrap();
     area.insert(entrance, '#');
-    area.insert(entrance.go(Up), '#');
-    area.insert(entrance.go(Down), '#');
-    area.insert(entrance.go(Left), '#');
-    area.insert(entrance.go(Right), '#');
+    for wall in entrance.neighbors4().iter() {
+        area.insert(*wall, '#');
+    }
     area.insert(entrance.go(Up).go(Left), '1');
     area.insert(entrance.go(Up).go(Right), '2');
     area.insert(entrance.go(Down).go(Left), '3');
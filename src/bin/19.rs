@@ -1,34 +1,64 @@
 use advent_of_code_2019::grid::{Grid, Location};
-use advent_of_code_2019::intcode::IntCode;
-
-fn check_location(program: &[i64], x: i64, y: i64) -> bool {
-    let mut ic = IntCode::new(program);
-    ic.input.push_back(x);
-    ic.input.push_back(y);
-    ic.run();
-    ic.output.pop().unwrap() != 0
+use advent_of_code_2019::intcode::controller::{drive, Controller};
+use advent_of_code_2019::intcode::pool::Pool;
+use advent_of_code_2019::log;
+use advent_of_code_2019::netpbm::{Format, Netpbm};
+
+// A drone sent to a location: it gives the coordinates, then reports
+// whether it is pulled by the beam.
+struct Probe {
+    coords: Vec<i64>,
+    pulled: Option<bool>,
+}
+
+impl Probe {
+    fn new(x: i64, y: i64) -> Probe {
+        Probe { coords: vec![y, x], pulled: None }
+    }
+}
+
+impl Controller for Probe {
+    type Frame = [i64; 1];
+
+    fn next_input(&mut self) -> Option<i64> {
+        self.coords.pop()
+    }
+
+    fn on_output(&mut self, [state]: [i64; 1]) {
+        self.pulled = Some(state != 0);
+    }
+}
+
+fn check_location(drones: &mut Pool, x: i64, y: i64) -> bool {
+    let mut drone = drones.get();
+    let mut probe = Probe::new(x, y);
+    drive(&mut drone, &mut probe);
+    drones.put(drone);
+    probe.pulled.expect("no report from the drone")
 }
 
 fn solve(input: &str) -> (usize, i64) {
     let program: Vec<_> = input.trim().split(',').map(|s| s.parse::<i64>().unwrap()).collect();
+    let mut drones = Pool::new(&program);
     let mut g = Grid::new();
     let mut affected = 0;
     for x in 0..50 {
         for y in 0..50 {
-            if check_location(&program, x, y) {
+            if check_location(&mut drones, x, y) {
                 affected += 1;
                 g.insert(Location { x, y }, '#');
             }
         }
     }
-    println!("{}", g);
+    log!("{}", g);
+    log::export("19.pbm", &Netpbm::new(&g, Format::Pbm).with_scale(4));
 
     let mut x = 0;
     let mut y = 0;
 
-    while !check_location(&program, x, y + 99) {
+    while !check_location(&mut drones, x, y + 99) {
         x += 1;
-        while !check_location(&program, x + 99, y) {
+        while !check_location(&mut drones, x + 99, y) {
             y += 1;
         }
     }
@@ -43,3 +73,19 @@ fn main() {
     println!("Solution: {:?}", s);
     println!("Time: {}ms", now.elapsed().as_millis());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example01() {
+        let mut probe = Probe::new(3, 7);
+        assert_eq!(probe.next_input(), Some(3));
+        assert_eq!(probe.next_input(), Some(7));
+        assert_eq!(probe.next_input(), None);
+        assert_eq!(probe.pulled, None);
+        probe.on_output([1]);
+        assert_eq!(probe.pulled, Some(true));
+    }
+}
@@ -1,5 +1,6 @@
 use advent_of_code_2019::graph::UnweightedGraph;
-use advent_of_code_2019::grid::{Direction::*, Grid, Location};
+use advent_of_code_2019::grid::{Direction::*, Grid, Layered, Location, Neighbors, WalkableGrid};
+use advent_of_code_2019::log;
 use std::collections::HashMap;
 
 struct GridWithPortals {
@@ -12,7 +13,7 @@ struct GridWithPortals {
 
 impl GridWithPortals {
     fn create_from(s: &str) -> GridWithPortals {
-        let grid = Grid::create_from(s);
+        let grid: Grid = Grid::create_from(s);
         let mut inner_tag = HashMap::new();
         let mut inner_portal = HashMap::new();
         let mut outer_tag = HashMap::new();
@@ -58,74 +59,35 @@ impl GridWithPortals {
             outer_portal,
         }
     }
-}
-
-impl UnweightedGraph<Location> for GridWithPortals {
-    fn edges(&self, node: &Location) -> Vec<Location> {
-        let mut v = Vec::new();
 
-        // regular move
-        for d in &[Up, Down, Left, Right] {
-            let next_node = node.go(*d);
-            if self.grid.get(&next_node) == Some(&'.') {
-                v.push(next_node);
-            }
-        }
-        // move from inner portal to outer portal
-        if let Some(s) = self.inner_tag.get(node) {
-            if let Some(next_node) = self.outer_portal.get(s) {
-                v.push(*next_node);
-            }
-        }
-        // move from outer portal to inner portal
-        if let Some(s) = self.outer_tag.get(node) {
-            if let Some(next_node) = self.inner_portal.get(s) {
-                v.push(*next_node);
-            }
-        }
-        v
+    // The maze without levels, where the portals join the inner and the
+    // outer tiles with the same tag.
+    fn walkable(&self) -> WalkableGrid<'_, char> {
+        let down = self.inner_tag.iter().filter_map(|(l, s)| Some((*l, *self.outer_portal.get(s)?)));
+        let up = self.outer_tag.iter().filter_map(|(l, s)| Some((*l, *self.inner_portal.get(s)?)));
+        WalkableGrid::new(&self.grid, |&c| c == '.').with_portals(down.chain(up))
     }
 }
 
-#[derive(Clone, Copy, Debug, Hash, PartialOrd, Ord, PartialEq, Eq)]
-struct LayeredLocation {
-    location: Location,
-    layer: usize,
-}
-
-impl UnweightedGraph<LayeredLocation> for GridWithPortals {
-    fn edges(&self, node: &LayeredLocation) -> Vec<LayeredLocation> {
-        let mut v = Vec::new();
-
+impl UnweightedGraph<Layered<Location>> for GridWithPortals {
+    fn edges(&self, node: &Layered<Location>) -> Vec<Layered<Location>> {
         // regular move
-        for d in &[Up, Down, Left, Right] {
-            let next_node = LayeredLocation {
-                location: node.location.go(*d),
-                layer: node.layer,
-            };
-            if self.grid.get(&next_node.location) == Some(&'.') {
-                v.push(next_node);
-            }
-        }
+        let mut v: Vec<_> = node
+            .neighbors()
+            .into_iter()
+            .filter(|next_node| self.grid.get(&next_node.point) == Some(&'.'))
+            .collect();
         // move from inner portal to outer portal
-        if let Some(s) = self.inner_tag.get(&node.location) {
+        if let Some(s) = self.inner_tag.get(&node.point) {
             if let Some(l) = self.outer_portal.get(s) {
-                let next_node = LayeredLocation {
-                    location: *l,
-                    layer: node.layer + 1,
-                };
-                v.push(next_node);
+                v.push(node.deeper(*l));
             }
         }
         // move from outer portal to inner portal
         if node.layer > 0 {
-            if let Some(s) = self.outer_tag.get(&node.location) {
+            if let Some(s) = self.outer_tag.get(&node.point) {
                 if let Some(l) = self.inner_portal.get(s) {
-                    let next_node = LayeredLocation {
-                        location: *l,
-                        layer: node.layer - 1,
-                    };
-                    v.push(next_node);
+                    v.push(node.shallower(*l));
                 }
             }
         }
@@ -135,20 +97,14 @@ impl UnweightedGraph<LayeredLocation> for GridWithPortals {
 
 fn solve(input: &str) -> (usize, usize) {
     let g = GridWithPortals::create_from(input);
-    println!("{}", g.grid);
+    log!("{}", g.grid);
 
     let start = g.outer_portal["AA"];
     let target = g.outer_portal["ZZ"];
-    let distances = g.shortest_paths(start, &[target]);
+    let distances = g.walkable().shortest_paths(start, &[target]);
 
-    let layered_start = LayeredLocation {
-        location: g.outer_portal["AA"],
-        layer: 0,
-    };
-    let layered_target = LayeredLocation {
-        location: g.outer_portal["ZZ"],
-        layer: 0,
-    };
+    let layered_start = Layered::new(0, g.outer_portal["AA"]);
+    let layered_target = Layered::new(0, g.outer_portal["ZZ"]);
     let layered_distances = g.shortest_paths(layered_start, &[layered_target]);
 
     (distances[&target], layered_distances[&layered_target])
@@ -192,16 +148,10 @@ FG..#########.....#
         );
         let start = g.outer_portal["AA"];
         let target = g.outer_portal["ZZ"];
-        let distances = g.shortest_paths(start, &[target]);
+        let distances = g.walkable().shortest_paths(start, &[target]);
         assert_eq!(distances[&target], 23);
-        let layered_start = LayeredLocation {
-            location: g.outer_portal["AA"],
-            layer: 0,
-        };
-        let layered_target = LayeredLocation {
-            location: g.outer_portal["ZZ"],
-            layer: 0,
-        };
+        let layered_start = Layered::new(0, g.outer_portal["AA"]);
+        let layered_target = Layered::new(0, g.outer_portal["ZZ"]);
         let layered_distances = g.shortest_paths(layered_start, &[layered_target]);
         assert_eq!(layered_distances[&layered_target], 26);
     }
@@ -250,7 +200,7 @@ YN......#               VT..#....QG
         );
         let start = g.outer_portal["AA"];
         let target = g.outer_portal["ZZ"];
-        let distances = g.shortest_paths(start, &[target]);
+        let distances = g.walkable().shortest_paths(start, &[target]);
         assert_eq!(distances[&target], 58);
     }
 
@@ -296,14 +246,8 @@ RE....#.#                           #......RF
                A O F   N                     
                A A D   M                     ",
         );
-        let layered_start = LayeredLocation {
-            location: g.outer_portal["AA"],
-            layer: 0,
-        };
-        let layered_target = LayeredLocation {
-            location: g.outer_portal["ZZ"],
-            layer: 0,
-        };
+        let layered_start = Layered::new(0, g.outer_portal["AA"]);
+        let layered_target = Layered::new(0, g.outer_portal["ZZ"]);
         let layered_distances = g.shortest_paths(layered_start, &[layered_target]);
         assert_eq!(layered_distances[&layered_target], 396);
     }
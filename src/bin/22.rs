@@ -17,6 +17,7 @@ impl Shuffle {
         Shuffle { p, a: 1, b: 0 }
     }
 
+    #[allow(clippy::manual_strip)]
     fn create_from(p: i64, input: &str) -> Shuffle {
         let mut shuffle = Shuffle::id(p);
         for s in input.lines() {
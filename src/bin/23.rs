@@ -1,53 +1,62 @@
+use advent_of_code_2019::intcode::frame::Frame;
+use advent_of_code_2019::intcode::scheduler::{Outcome, Scheduler};
 use advent_of_code_2019::intcode::IntCode;
 
+struct Packet {
+    dest: usize,
+    x: i64,
+    y: i64,
+}
+
+impl Frame for Packet {
+    const LEN: usize = 3;
+
+    fn decode(values: &[i64]) -> Packet {
+        Packet {
+            dest: values[0] as usize,
+            x: values[1],
+            y: values[2],
+        }
+    }
+}
+
 fn solve(input: &str) -> (i64, i64) {
     let program: Vec<_> = input.trim().split(',').map(|s| s.parse::<i64>().unwrap()).collect();
-    let mut computers: Vec<_> = (0..50)
+    let computers = (0..50)
         .map(|i| {
             let mut c = IntCode::new(&program);
             c.input.push_back(i as i64);
-            c.run();
             c
         })
         .collect();
-    let mut nat = (0, 0);
+    let mut network = Scheduler::new(computers).with_idle_input(-1);
+    let mut nat = None;
     let mut first_nat_received = None;
     let mut last_nat_sent = None;
-    let mut idle;
 
     loop {
-        idle = true;
-        for i in 0..50 {
-            // process input received by computer i
-            if computers[i].input.is_empty() {
-                computers[i].input.push_back(-1);
-            }
-            computers[i].run();
-            // dispatch packets from computer i
-            let u: Vec<_> = computers[i].output.drain(..).collect();
-            for pkt in u.chunks(3) {
-                idle = false;
-                let dest = pkt[0] as usize;
-                if dest == 255 {
-                    nat = (pkt[1], pkt[2]);
-                    if first_nat_received.is_none() {
-                        first_nat_received = Some(nat)
-                    }
+        let outcome = network.run(|_, c| {
+            let mut delivered = Vec::new();
+            for pkt in c.frames::<Packet>() {
+                if pkt.dest == 255 {
+                    nat = Some((pkt.x, pkt.y));
+                    first_nat_received.get_or_insert((pkt.x, pkt.y));
                 } else {
-                    computers[dest].input.push_back(pkt[1]);
-                    computers[dest].input.push_back(pkt[2]);
+                    delivered.push((pkt.dest, pkt.x));
+                    delivered.push((pkt.dest, pkt.y));
                 }
             }
+            delivered
+        });
+        assert_eq!(outcome, Outcome::Deadlock);
+
+        // the network is idle: send the nat packet to computer 0
+        let (x, y) = nat.expect("idle network without nat packet");
+        if last_nat_sent.filter(|(_, last_y)| *last_y == y).is_some() {
+            break;
         }
-        if idle {
-            // send nat packet to computer 0
-            computers[0].input.push_back(nat.0);
-            computers[0].input.push_back(nat.1);
-            if last_nat_sent.filter(|(_, y)| *y == nat.1).is_some() {
-                break;
-            }
-            last_nat_sent = Some(nat);
-        }
+        network.send(0, &[x, y]);
+        last_nat_sent = Some((x, y));
     }
 
     (first_nat_received.unwrap().1, last_nat_sent.unwrap().1)
@@ -1,28 +1,54 @@
-use advent_of_code_2019::grid::{Direction::*, Grid, Location};
-use std::collections::HashMap;
+use advent_of_code_2019::grid::bitboard::{Bitboard, Levels};
+use advent_of_code_2019::grid::{Grid, Location};
+use advent_of_code_2019::log;
 use std::collections::HashSet;
 
-// This struct stores the positions of the bugs as pairs
-// (level, x-y coordinates).
-struct BugsMap(HashSet<(i64, Location)>);
+// A bug survives with exactly one bug next to it, and an empty space
+// becomes infested with one or two.
+const BIRTH: &[usize] = &[1, 2];
+const SURVIVAL: &[usize] = &[1];
 
-impl BugsMap {
-    fn create_from(s: &str) -> BugsMap {
-        let mut bugs = HashSet::new();
-        let g = Grid::create_from(s);
-        for (l, c) in g.symbols.iter() {
-            if c == &'#' {
-                bugs.insert((0, *l));
-            }
+// The bugs of an area, one per bit. The biodiversity rating is the
+// number itself.
+type Area = Bitboard<5, 5>;
+
+fn area(s: &str) -> Area {
+    Area::from_grid(&Grid::create_with(s, |c| Some(c == '#')), |&bug| bug)
+}
+
+fn advance(bugs: Area) -> Area {
+    bugs.step(&bugs.von_neumann(), BIRTH, SURVIVAL)
+}
+
+// The first layout appearing twice.
+fn first_repeat(mut bugs: Area) -> Area {
+    let mut seen = HashSet::new();
+    while seen.insert(bugs) {
+        bugs = advance(bugs);
+    }
+    bugs
+}
+
+fn to_string(bugs: &Area) -> String {
+    let mut s = String::new();
+    for y in 0..5 {
+        for x in 0..5 {
+            s.push(if bugs.is_alive(Location { x, y }) { '#' } else { '.' });
         }
-        BugsMap(bugs)
+        s.push('\n');
     }
+    s
+}
 
-    fn to_string(&self) -> String {
-        let mut s = String::new();
+fn to_string_multilevel(bugs: &Levels<5, 5>) -> String {
+    let mut s = String::new();
+    for z in bugs.layers() {
+        s.push_str(&format!("Depth {}:\n", z));
         for y in 0..5 {
             for x in 0..5 {
-                if self.0.contains(&(0, Location { x, y })) {
+                if x == 2 && y == 2 {
+                    s.push('?');
+                } else if bugs.level(z).is_alive(Location { x, y }) {
                     s.push('#');
                 } else {
                     s.push('.');
@@ -30,197 +56,22 @@ impl BugsMap {
             }
             s.push('\n');
         }
-        s
-    }
-
-    fn to_string_multilevel(&self) -> String {
-        let mut s = String::new();
-        let z_min = *self.0.iter().map(|(z, _)| z).min().unwrap_or(&0);
-        let z_max = *self.0.iter().map(|(z, _)| z).max().unwrap_or(&0);
-        for z in z_min..=z_max {
-            s.push_str(&format!("Depth {}:\n", z));
-            for y in 0..5 {
-                for x in 0..5 {
-                    if x == 2 && y == 2 {
-                        s.push('?');
-                    } else if self.0.contains(&(z, Location { x, y })) {
-                        s.push('#');
-                    } else {
-                        s.push('.');
-                    }
-                }
-                s.push('\n');
-            }
-        }
-        s
-    }
-
-    fn biodiversity(&self) -> usize {
-        let mut rating = 0;
-        let mut points = 1;
-        for y in 0..5 {
-            for x in 0..5 {
-                if self.0.contains(&(0, Location { x, y })) {
-                    rating += points;
-                }
-                points *= 2;
-            }
-        }
-        rating
-    }
-
-    fn advance(&mut self) {
-        // count neighbouring bugs
-        let mut count = HashMap::new();
-        for (level, location) in self.0.iter() {
-            count.entry((*level, *location)).or_insert(0);
-            match location.x {
-                0 => { *count.entry((*level, location.go(Right))).or_insert(0) += 1; },
-                4 => { *count.entry((*level, location.go(Left))).or_insert(0) += 1; },
-                1|2|3 => {
-                    *count.entry((*level, location.go(Left))).or_insert(0) += 1;
-                    *count.entry((*level, location.go(Right))).or_insert(0) += 1;
-                },
-                _ => { panic!(); },
-            }
-            match location.y {
-                0 => { *count.entry((*level, location.go(Down))).or_insert(0) += 1; },
-                4 => { *count.entry((*level, location.go(Up))).or_insert(0) += 1; },
-                1|2|3 => {
-                    *count.entry((*level, location.go(Up))).or_insert(0) += 1;
-                    *count.entry((*level, location.go(Down))).or_insert(0) += 1;
-                },
-                _ => { panic!(); },
-            }
-        }
-
-        // update bug locations
-        for (bug, n) in count.into_iter() {
-            if self.0.contains(&bug) {
-                match n {
-                    1 => {},
-                    _ => { self.0.remove(&bug); },
-                }
-            } else {
-                match n {
-                    1|2 => { self.0.insert(bug); },
-                    _   => {},
-                }
-            }
-        }
-    }
-    
-    fn advance_multilevel( &mut self ) {
-        // count neighbouring bugs
-        let mut count = HashMap::new();
-        for (level, location) in self.0.iter() {
-            count.entry((*level, *location)).or_insert(0);
-            match (location.x, location.y) {
-                (0,_) => {
-                    *count.entry((*level - 1, Location{ x: 1, y: 2 })).or_insert(0) += 1;
-                    *count.entry((*level, location.go(Right))).or_insert(0) += 1;
-                },
-                (4,_) => {
-                    *count.entry((*level, location.go(Left))).or_insert(0) += 1;
-                    *count.entry((*level - 1, Location{ x: 3, y: 2 })).or_insert(0) += 1;
-                },
-                (_,0)|(_,1)|(_,3)|(_,4) => {
-                    *count.entry((*level, location.go(Left))).or_insert(0) += 1;
-                    *count.entry((*level, location.go(Right))).or_insert(0) += 1;
-                },
-                (1,2) => {
-                    *count.entry((*level, location.go(Left))).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 0, y: 0 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 0, y: 1 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 0, y: 2 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 0, y: 3 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 0, y: 4 })).or_insert(0) += 1;
-                },
-                (3,2) => {
-                    *count.entry((*level + 1, Location{ x: 4, y: 0 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 4, y: 1 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 4, y: 2 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 4, y: 3 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 4, y: 4 })).or_insert(0) += 1;
-                    *count.entry((*level, location.go(Right))).or_insert(0) += 1;
-                },
-                _ => { panic!(); },
-            }
-            match (location.x, location.y) {
-                (_,0) => {
-                    *count.entry((*level - 1, Location{ x: 2, y: 1 })).or_insert(0) += 1;
-                    *count.entry((*level, location.go(Down))).or_insert(0) += 1;
-                },
-                (_,4) => {
-                    *count.entry((*level, location.go(Up))).or_insert(0) += 1;
-                    *count.entry((*level - 1, Location{ x: 2, y: 3 })).or_insert(0) += 1;
-                },
-                (0,_)|(1,_)|(3,_)|(4,_) => {
-                    *count.entry((*level, location.go(Up))).or_insert(0) += 1;
-                    *count.entry((*level, location.go(Down))).or_insert(0) += 1;
-                },
-                (2,1) => {
-                    *count.entry((*level, location.go(Up))).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 0, y: 0 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 1, y: 0 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 2, y: 0 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 3, y: 0 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 4, y: 0 })).or_insert(0) += 1;
-                },
-                (2,3) => {
-                    *count.entry((*level + 1, Location{ x: 0, y: 4 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 1, y: 4 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 2, y: 4 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 3, y: 4 })).or_insert(0) += 1;
-                    *count.entry((*level + 1, Location{ x: 4, y: 4 })).or_insert(0) += 1;
-                    *count.entry((*level, location.go(Down))).or_insert(0) += 1;
-                },
-                _ => { panic!(); },
-            }
-        }
-
-        // update bug locations
-        for (bug, n) in count.into_iter() {
-            if self.0.contains(&bug) {
-                match n {
-                    1 => {}
-                    _ => {
-                        self.0.remove(&bug);
-                    }
-                }
-            } else {
-                match n {
-                    1 | 2 => {
-                        self.0.insert(bug);
-                    }
-                    _ => {}
-                }
-            }
-        }
     }
+    s
 }
 
 fn solve(input: &str, n: usize) -> (usize, usize) {
-    let mut bugs = BugsMap::create_from(input);
-    println!("{}", bugs.to_string());
-
-    let mut seen_b = HashSet::new();
-    let mut current_b = bugs.biodiversity();
-    while !seen_b.contains(&current_b) {
-        seen_b.insert(current_b);
-        bugs.advance();
-        current_b = bugs.biodiversity();
-    }
-    //println!("{}", bugs.to_string());
+    let bugs = area(input);
+    log!("{}", to_string(&bugs));
+    let repeat = first_repeat(bugs);
 
-    let mut bugs = BugsMap::create_from(input);
-    println!("{}", bugs.to_string_multilevel());
+    let mut levels = Levels::new(bugs);
     for _ in 0..n {
-        bugs.advance_multilevel();
+        levels = levels.step(BIRTH, SURVIVAL);
     }
-    //println!("{}", bugs.to_string_multilevel());
+    log!("{}", to_string_multilevel(&levels));
 
-    (current_b, bugs.0.len())
+    (repeat.0 as usize, levels.len())
 }
 
 fn main() {
@@ -237,7 +88,7 @@ mod tests {
 
     #[test]
     fn example01() {
-        let mut bugs = BugsMap::create_from(
+        let mut bugs = area(
             "\
 ....#
 #..#.
@@ -245,9 +96,9 @@ mod tests {
 ..#..
 #....",
         );
-        bugs.advance();
+        bugs = advance(bugs);
         assert_eq!(
-            bugs.to_string(),
+            to_string(&bugs),
             "\
 #..#.
 ####.
@@ -256,9 +107,9 @@ mod tests {
 .##..
 "
         );
-        bugs.advance();
+        bugs = advance(bugs);
         assert_eq!(
-            bugs.to_string(),
+            to_string(&bugs),
             "\
 #####
 ....#
@@ -267,9 +118,9 @@ mod tests {
 #.###
 "
         );
-        bugs.advance();
+        bugs = advance(bugs);
         assert_eq!(
-            bugs.to_string(),
+            to_string(&bugs),
             "\
 #....
 ####.
@@ -278,9 +129,9 @@ mod tests {
 .##.#
 "
         );
-        bugs.advance();
+        bugs = advance(bugs);
         assert_eq!(
-            bugs.to_string(),
+            to_string(&bugs),
             "\
 ####.
 ....#
@@ -289,15 +140,9 @@ mod tests {
 ##...
 "
         );
-        let mut seen_b = HashSet::new();
-        let mut current_b = bugs.biodiversity();
-        while !seen_b.contains(&current_b) {
-            seen_b.insert(current_b);
-            bugs.advance();
-            current_b = bugs.biodiversity();
-        }
+        let bugs = first_repeat(bugs);
         assert_eq!(
-            bugs.to_string(),
+            to_string(&bugs),
             "\
 .....
 .....
@@ -306,24 +151,24 @@ mod tests {
 .#...
 "
         );
-        assert_eq!(current_b, 2129920);
+        assert_eq!(bugs.0, 2129920);
     }
 
     #[test]
     fn example02() {
-        let mut bugs = BugsMap::create_from(
+        let mut bugs = Levels::new(area(
             "\
 ....#
 #..#.
 #.?##
 ..#..
 #....",
-        );
+        ));
         for _ in 0..10 {
-            bugs.advance_multilevel();
+            bugs = bugs.step(BIRTH, SURVIVAL);
         }
         assert_eq!(
-            bugs.to_string_multilevel(),
+            to_string_multilevel(&bugs),
             "\
 Depth -5:
 ..#..
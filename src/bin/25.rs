@@ -1,6 +1,8 @@
 use advent_of_code_2019::graph::Backtracking;
-use advent_of_code_2019::grid::Direction::{self, *};
-use advent_of_code_2019::intcode::IntCode;
+use advent_of_code_2019::grid::Direction;
+use advent_of_code_2019::intcode::observer::{Stdout, Transcript};
+use advent_of_code_2019::intcode::{IntCode, Machine};
+use advent_of_code_2019::log;
 use std::cmp::Ordering;
 use std::collections::HashSet;
 
@@ -18,8 +20,8 @@ enum Goal {
 }
 
 #[derive(Clone)]
-struct Droid {
-    computer: IntCode,
+struct Droid<M> {
+    computer: M,
     room: String,
     doors: Vec<Direction>,
     last_move: Option<Direction>,
@@ -30,10 +32,10 @@ struct Droid {
     goal: Goal,
 }
 
-impl Droid {
-    fn new(program: &[i64]) -> Droid {
+impl<M: Machine> Droid<M> {
+    fn new(computer: M) -> Droid<M> {
         let mut d = Droid {
-            computer: IntCode::new(program),
+            computer,
             room: String::new(),
             doors: Vec::new(),
             last_move: None,
@@ -43,7 +45,6 @@ impl Droid {
             checkpoint_status: Ordering::Less,
             goal: Goal::Explore,
         };
-        d.computer.display = false;
 
         // program the droid to avoid trap objects
         d.trap_objects.insert("molten lava".to_string());
@@ -57,21 +58,20 @@ impl Droid {
         d
     }
 
+    #[allow(clippy::manual_strip)]
     fn read_room(&mut self, room: String) {
         self.room = room;
         self.doors.clear();
         for l in self.room.lines() {
             if l.starts_with("- ") {
-                match &l[2..] {
-                    "north" => self.doors.push(Up),
-                    "east" => self.doors.push(Right),
-                    "south" => self.doors.push(Down),
-                    "west" => self.doors.push(Left),
-                    obj => {
-                        if !self.trap_objects.contains(obj) {
-                            self.computer.run_ascii_command(&format!("take {}", obj));
-                            self.objects_carried.insert(obj.to_string());
-                            println!("Found {} in {}!", obj, self.room.lines().nth(3).unwrap());
+                let item = &l[2..];
+                match Direction::ALL.iter().find(|d| d.name() == item) {
+                    Some(d) => self.doors.push(*d),
+                    None => {
+                        if !self.trap_objects.contains(item) {
+                            self.computer.run_ascii_command(&format!("take {}", item));
+                            self.objects_carried.insert(item.to_string());
+                            log!("Found {} in {}!", item, self.room.lines().nth(3).unwrap());
                         }
                     }
                 }
@@ -80,12 +80,7 @@ impl Droid {
     }
 
     fn try_checkpoint(&mut self) {
-        self.room = match self.checkpoint_move.unwrap() {
-            Up => self.computer.run_ascii_command("north").0,
-            Right => self.computer.run_ascii_command("east").0,
-            Down => self.computer.run_ascii_command("south").0,
-            Left => self.computer.run_ascii_command("west").0,
-        };
+        self.room = self.computer.run_ascii_command(self.checkpoint_move.unwrap().name()).0;
         if self.room.contains("lighter than the detected value") {
             self.checkpoint_status = Ordering::Greater;
         } else if self.room.contains("heavier than the detected value") {
@@ -96,7 +91,7 @@ impl Droid {
     }
 }
 
-impl Backtracking for Droid {
+impl<M: Machine> Backtracking for Droid<M> {
     type Action = DroidAction;
 
     fn list_actions(&self) -> Vec<DroidAction> {
@@ -129,12 +124,7 @@ impl Backtracking for Droid {
     fn try_action(&mut self, action: &DroidAction) -> bool {
         match action {
             DroidAction::Move(d) => {
-                let room = match d {
-                    Up => self.computer.run_ascii_command("north").0,
-                    Right => self.computer.run_ascii_command("east").0,
-                    Down => self.computer.run_ascii_command("south").0,
-                    Left => self.computer.run_ascii_command("west").0,
-                };
+                let room = self.computer.run_ascii_command(d.name()).0;
                 if room.contains("ejected back to the checkpoint") {
                     self.checkpoint_move = Some(*d);
                     return false;
@@ -153,10 +143,7 @@ impl Backtracking for Droid {
 
     fn backtrack(&mut self, action: &DroidAction) {
         let room = match action {
-            DroidAction::Move(Up) => self.computer.run_ascii_command("south").0,
-            DroidAction::Move(Right) => self.computer.run_ascii_command("west").0,
-            DroidAction::Move(Down) => self.computer.run_ascii_command("north").0,
-            DroidAction::Move(Left) => self.computer.run_ascii_command("east").0,
+            DroidAction::Move(d) => self.computer.run_ascii_command(d.reverse().name()).0,
             DroidAction::Drop(obj) => {
                 self.objects_carried.insert(obj.clone());
                 self.computer.run_ascii_command(&format!("take {}", obj)).0
@@ -175,40 +162,56 @@ impl Backtracking for Droid {
     }
 }
 
-fn solve(input: &str) -> i64 {
-    let program: Vec<_> = input.trim().split(',').map(|s| s.parse::<i64>().unwrap()).collect();
-
+// Explores the ship picking up objects, then goes through the checkpoint
+// dropping objects until the weight is right. Returns the state of the
+// droid before going to the checkpoint, and the actions from there.
+fn break_in<M: Machine + Clone>(droid: &mut Droid<M>) -> Result<(Droid<M>, Vec<DroidAction>), &'static str> {
     // Explore the environment and pick up objects.
-    let mut droid = Droid::new(&program);
     droid.explore();
 
     // Save the current state.
-    let mut saved_state = droid.clone();
+    let saved_state = droid.clone();
 
     // Go to the checkpoint.
     droid.goal = Goal::GoToCheckpoint;
-    let winning_moves = droid.explore().expect("checkpoint not found");
+    let winning_moves = droid.explore().ok_or("checkpoint not found")?;
 
     // Try to drop objects until we pass the checkpoint.
     // Backtrack if we get too light.
     droid.goal = Goal::VerifyIdentity;
     droid.try_checkpoint();
-    let winning_drops = droid.explore().expect("solution not found");
+    let winning_drops = droid.explore().ok_or("solution not found")?;
 
-    let password = droid
+    Ok((saved_state, winning_moves.into_iter().chain(winning_drops).collect()))
+}
+
+fn password<M>(droid: &Droid<M>) -> i64 {
+    droid
         .room
         .split(' ')
         .find_map(|w| w.parse::<i64>().ok())
-        .unwrap();
+        .unwrap()
+}
 
-    // Replay the winning moves with display on.
-    print!("{}", saved_state.room);
-    saved_state.computer.display = true;
-    for a in winning_moves.iter().chain(winning_drops.iter()) {
-        saved_state.try_action(a);
+fn solve(input: &str) -> i64 {
+    let program: Vec<_> = input.trim().split(',').map(|s| s.parse::<i64>().unwrap()).collect();
+
+    let mut computer = IntCode::new(&program);
+    let transcript = computer.observe(Transcript::default());
+    let mut droid = Droid::new(computer);
+    let (mut saved_state, winning_actions) =
+        break_in(&mut droid).unwrap_or_else(|e| panic!("{}\n{}", e, transcript.borrow()));
+
+    // Replay the winning moves showing the output.
+    if log::enabled() {
+        print!("{}", saved_state.room);
+        saved_state.computer.observe(Stdout);
+        for a in winning_actions.iter() {
+            saved_state.try_action(a);
+        }
     }
 
-    password
+    password(&droid)
 }
 
 fn main() {
@@ -218,3 +221,79 @@ fn main() {
     println!("Solution: {:?}", s);
     println!("Time: {}ms", now.elapsed().as_millis());
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use advent_of_code_2019::intcode::mock::Text;
+    use std::collections::HashMap;
+
+    // A small ship: the floor behind the checkpoint lets through droids
+    // carrying exactly 7 units of weight.
+    fn ship() -> impl Machine + Clone {
+        let mut doors = HashMap::new();
+        doors.insert("Hull Breach", vec![("north", "Kitchen"), ("east", "Hallway")]);
+        doors.insert("Kitchen", vec![("south", "Hull Breach")]);
+        doors.insert("Hallway", vec![("west", "Hull Breach"), ("east", "Security Checkpoint")]);
+        doors.insert("Security Checkpoint", vec![("west", "Hallway"), ("north", "Pressure-Sensitive Floor")]);
+        let mut items: HashMap<&str, Vec<String>> = HashMap::new();
+        items.insert("Kitchen", vec!["mug".to_string(), "molten lava".to_string()]);
+        items.insert("Hallway", vec!["book".to_string()]);
+        items.insert("Security Checkpoint", vec!["coin".to_string()]);
+        let weights: HashMap<&str, i64> = [("mug", 2), ("book", 4), ("coin", 5)].iter().cloned().collect();
+
+        let exits = doors.clone();
+        let describe = move |room: &str, items: &HashMap<&str, Vec<String>>| {
+            let mut s = format!("\n\n\n== {} ==\nA room.\n\nDoors here lead:\n", room);
+            for (d, _) in exits[room].iter() {
+                s += &format!("- {}\n", d);
+            }
+            if let Some(items) = items.get(room).filter(|i| !i.is_empty()) {
+                s += "\nItems here:\n";
+                for i in items.iter() {
+                    s += &format!("- {}\n", i);
+                }
+            }
+            s + "\nCommand?\n"
+        };
+        let mut room = "Hull Breach";
+        let mut carried: Vec<String> = Vec::new();
+        let intro = describe(room, &items);
+        Text::new(&intro, move |command| {
+            let reply = if let Some(obj) = command.strip_prefix("take ") {
+                items.get_mut(room)?.retain(|i| i != obj);
+                carried.push(obj.to_string());
+                format!("\nYou take the {}.\n\nCommand?\n", obj)
+            } else if let Some(obj) = command.strip_prefix("drop ") {
+                carried.retain(|i| i != obj);
+                items.entry(room).or_default().push(obj.to_string());
+                format!("\nYou drop the {}.\n\nCommand?\n", obj)
+            } else {
+                let next = doors[room].iter().find(|(d, _)| *d == command)?.1;
+                if next != "Pressure-Sensitive Floor" {
+                    room = next;
+                    return Some(describe(room, &items));
+                }
+                let weight: i64 = carried.iter().map(|i| weights[i.as_str()]).sum();
+                match weight.cmp(&7) {
+                    Ordering::Less => "Droids on this ship are heavier than the detected value! \
+                                       You are ejected back to the checkpoint.\n".to_string(),
+                    Ordering::Greater => "Droids on this ship are lighter than the detected value! \
+                                          You are ejected back to the checkpoint.\n".to_string(),
+                    Ordering::Equal => return Some("You should be able to get in by typing 4242 on the keypad.\n"
+                        .to_string()),
+                }
+            };
+            Some(reply)
+        })
+    }
+
+    #[test]
+    fn example01() {
+        let mut droid = Droid::new(ship());
+        let (_, actions) = break_in(&mut droid).unwrap();
+        assert_eq!(password(&droid), 4242);
+        assert_eq!(actions.len(), 3);
+        assert!(!droid.objects_carried.contains("molten lava"));
+    }
+}
@@ -0,0 +1,225 @@
+// Directions on a map where y grows downwards: the four directions of
+// the robots, and the eight directions of the compass rose. Both parse
+// from the encodings used by the puzzles (arrows, letters, names) and
+// can be rotated and converted to a `(dx, dy)` step.
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    // clockwise, from Up
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+    pub fn left(self) -> Direction {
+        match self {
+            Self::Up => Self::Left,
+            Self::Down => Self::Right,
+            Self::Left => Self::Down,
+            Self::Right => Self::Up,
+        }
+    }
+
+    pub fn right(self) -> Direction {
+        match self {
+            Self::Up => Self::Right,
+            Self::Down => Self::Left,
+            Self::Left => Self::Up,
+            Self::Right => Self::Down,
+        }
+    }
+
+    pub fn reverse(self) -> Direction {
+        match self {
+            Self::Up => Self::Down,
+            Self::Down => Self::Up,
+            Self::Left => Self::Right,
+            Self::Right => Self::Left,
+        }
+    }
+
+    // Rotates clockwise by a number of quarter turns (counterclockwise if
+    // negative).
+    pub fn rotate(self, quarters: i64) -> Direction {
+        let i = Direction::ALL.iter().position(|d| *d == self).unwrap() as i64;
+        Direction::ALL[(i + quarters).rem_euclid(4) as usize]
+    }
+
+    pub fn delta(self) -> (i64, i64) {
+        match self {
+            Self::Up => (0, -1),
+            Self::Down => (0, 1),
+            Self::Left => (-1, 0),
+            Self::Right => (1, 0),
+        }
+    }
+
+    // The movement commands of the repair droid: north (1), south (2),
+    // west (3) and east (4).
+    pub fn code(self) -> i64 {
+        match self {
+            Self::Up => 1,
+            Self::Down => 2,
+            Self::Left => 3,
+            Self::Right => 4,
+        }
+    }
+
+    pub fn from_code(code: i64) -> Option<Direction> {
+        match code {
+            1 => Some(Self::Up),
+            2 => Some(Self::Down),
+            3 => Some(Self::Left),
+            4 => Some(Self::Right),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Up => "north",
+            Self::Down => "south",
+            Self::Left => "west",
+            Self::Right => "east",
+        }
+    }
+}
+
+// Parses "U", "^", "N" and "north" (and so on) for the four directions.
+impl FromStr for Direction {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Direction, String> {
+        match s {
+            "U" | "^" | "N" | "north" => Ok(Self::Up),
+            "D" | "v" | "S" | "south" => Ok(Self::Down),
+            "L" | "<" | "W" | "west" => Ok(Self::Left),
+            "R" | ">" | "E" | "east" => Ok(Self::Right),
+            _ => Err(format!("invalid direction {:?}", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub enum Compass {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Compass {
+    // clockwise, from N
+    pub const ALL: [Compass; 8] = [
+        Compass::N,
+        Compass::NE,
+        Compass::E,
+        Compass::SE,
+        Compass::S,
+        Compass::SW,
+        Compass::W,
+        Compass::NW,
+    ];
+
+    // Rotates clockwise by a number of eighths of a turn (counterclockwise
+    // if negative).
+    pub fn rotate(self, eighths: i64) -> Compass {
+        let i = Compass::ALL.iter().position(|d| *d == self).unwrap() as i64;
+        Compass::ALL[(i + eighths).rem_euclid(8) as usize]
+    }
+
+    pub fn reverse(self) -> Compass {
+        self.rotate(4)
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        matches!(self, Self::NE | Self::SE | Self::SW | Self::NW)
+    }
+
+    pub fn delta(self) -> (i64, i64) {
+        match self {
+            Self::N => (0, -1),
+            Self::NE => (1, -1),
+            Self::E => (1, 0),
+            Self::SE => (1, 1),
+            Self::S => (0, 1),
+            Self::SW => (-1, 1),
+            Self::W => (-1, 0),
+            Self::NW => (-1, -1),
+        }
+    }
+}
+
+impl From<Direction> for Compass {
+    fn from(d: Direction) -> Compass {
+        match d {
+            Direction::Up => Compass::N,
+            Direction::Down => Compass::S,
+            Direction::Left => Compass::W,
+            Direction::Right => Compass::E,
+        }
+    }
+}
+
+// Parses the abbreviations ("NE") and the names ("northeast"), and
+// anything a `Direction` parses from.
+impl FromStr for Compass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Compass, String> {
+        match s {
+            "NE" | "northeast" => Ok(Self::NE),
+            "SE" | "southeast" => Ok(Self::SE),
+            "SW" | "southwest" => Ok(Self::SW),
+            "NW" | "northwest" => Ok(Self::NW),
+            _ => s.parse::<Direction>().map(Compass::from).map_err(|_| format!("invalid direction {:?}", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotations() {
+        for d in Direction::ALL.iter() {
+            assert_eq!(d.rotate(1), d.right());
+            assert_eq!(d.rotate(-1), d.left());
+            assert_eq!(d.rotate(2), d.reverse());
+            assert_eq!(d.rotate(-7), d.right());
+            assert_eq!(Compass::from(*d).rotate(2), Compass::from(d.right()));
+        }
+        assert_eq!(Compass::N.rotate(-1), Compass::NW);
+        assert_eq!(Compass::SW.reverse(), Compass::NE);
+        assert_eq!(Compass::ALL.iter().filter(|c| c.is_diagonal()).count(), 4);
+        let sum = Compass::ALL.iter().map(|c| c.delta()).fold((0, 0), |(x, y), (dx, dy)| (x + dx, y + dy));
+        assert_eq!(sum, (0, 0));
+    }
+
+    #[test]
+    fn parsing() {
+        for (s, d) in [("U", Direction::Up), ("v", Direction::Down), ("west", Direction::Left), ("E", Direction::Right)]
+            .iter()
+        {
+            assert_eq!(s.parse::<Direction>(), Ok(*d));
+        }
+        assert!("up".parse::<Direction>().is_err());
+        assert_eq!("northwest".parse::<Compass>(), Ok(Compass::NW));
+        assert_eq!(">".parse::<Compass>(), Ok(Compass::E));
+        assert_eq!("NNE".parse::<Compass>(), Err("invalid direction \"NNE\"".to_string()));
+        for d in Direction::ALL.iter() {
+            assert_eq!(Direction::from_code(d.code()), Some(*d));
+            assert_eq!(d.name().parse::<Direction>(), Ok(*d));
+        }
+    }
+}
@@ -1,125 +1,541 @@
-use std::collections::HashMap;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
+use std::ops::RangeInclusive;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Direction {
-    Up,
-    Down,
-    Left,
-    Right,
+pub mod automaton;
+pub mod bitboard;
+pub mod point;
+pub mod sight;
+pub mod transform;
+pub mod walkable;
+
+pub use crate::direction::{Compass, Direction};
+pub use point::{Layered, Neighbors, Point3, PointN};
+pub use sight::angular_sort;
+pub use transform::SubGrid;
+pub use walkable::WalkableGrid;
+
+#[derive(Clone, Copy, Debug, Hash, PartialOrd, Ord, PartialEq, Eq)]
+pub struct Location {
+    pub x: i64, // steps right from the reference point
+    pub y: i64, // steps down from the reference point
+}
+
+impl Location {
+    // The next location in a direction (a `Direction` or a `Compass`).
+    pub fn go(&self, direction: impl Into<Compass>) -> Location {
+        let (dx, dy) = direction.into().delta();
+        Location { x: self.x + dx, y: self.y + dy }
+    }
+
+    // The locations up, down, left and right.
+    pub fn neighbors4(&self) -> [Location; 4] {
+        [self.go(Direction::Up), self.go(Direction::Down), self.go(Direction::Left), self.go(Direction::Right)]
+    }
+
+    // The locations around, diagonals included, row by row.
+    pub fn neighbors8(&self) -> [Location; 8] {
+        let (x, y) = (self.x, self.y);
+        [
+            Location { x: x - 1, y: y - 1 }, Location { x, y: y - 1 }, Location { x: x + 1, y: y - 1 },
+            Location { x: x - 1, y },                                   Location { x: x + 1, y },
+            Location { x: x - 1, y: y + 1 }, Location { x, y: y + 1 }, Location { x: x + 1, y: y + 1 },
+        ]
+    }
+
+    // The number of steps up, down, left or right to go to `other`.
+    pub fn manhattan(&self, other: &Location) -> i64 {
+        (self.x - other.x).abs() + (self.y - other.y).abs()
+    }
+
+    // The number of steps to go to `other`, diagonals included.
+    pub fn chebyshev(&self, other: &Location) -> i64 {
+        (self.x - other.x).abs().max((self.y - other.y).abs())
+    }
+}
+
+// The cells of a grid: a hash map for unbounded worlds, or a vector
+// covering a rectangle for maps with most of their cells set.
+#[derive(Clone, Debug)]
+enum Cells<T> {
+    Sparse(HashMap<Location, T>),
+    Dense {
+        origin: Location, // the top left corner of the rectangle
+        width: usize,
+        height: usize,
+        cells: Vec<Option<(Location, T)>>,
+        len: usize, // the number of cells set
+    },
 }
 
-impl Direction {
-    pub fn left(self) -> Direction {
-        match self {
-            Self::Up => Self::Left,
-            Self::Down => Self::Right,
-            Self::Left => Self::Down,
-            Self::Right => Self::Up,
+// The cells set in a grid, readable like a `HashMap<Location, T>`
+// whatever the storage.
+#[derive(Clone, Debug)]
+pub struct Symbols<T>(Cells<T>);
+
+impl<T> Default for Symbols<T> {
+    fn default() -> Symbols<T> {
+        Symbols(Cells::Sparse(HashMap::new()))
+    }
+}
+
+impl<T> Symbols<T> {
+    pub fn len(&self) -> usize {
+        match &self.0 {
+            Cells::Sparse(symbols) => symbols.len(),
+            Cells::Dense { len, .. } => *len,
         }
     }
 
-    pub fn right(self) -> Direction {
-        match self {
-            Self::Up => Self::Right,
-            Self::Down => Self::Left,
-            Self::Left => Self::Up,
-            Self::Right => Self::Down,
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, l: &Location) -> Option<&T> {
+        match &self.0 {
+            Cells::Sparse(symbols) => symbols.get(l),
+            Cells::Dense { cells, .. } => self.index(l).and_then(|i| cells[i].as_ref()).map(|(_, c)| c),
         }
     }
 
-    pub fn reverse(self) -> Direction {
-        match self {
-            Self::Up => Self::Down,
-            Self::Down => Self::Up,
-            Self::Left => Self::Right,
-            Self::Right => Self::Left,
+    pub fn contains_key(&self, l: &Location) -> bool {
+        self.get(l).is_some()
+    }
+
+    // Iterates over the cells set, in no particular order.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (&Location, &T)> + '_> {
+        match &self.0 {
+            Cells::Sparse(symbols) => Box::new(symbols.iter()),
+            Cells::Dense { cells, .. } => Box::new(cells.iter().flatten().map(|(l, c)| (l, c))),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Location> + '_ {
+        self.iter().map(|(l, _)| l)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_ {
+        self.iter().map(|(_, c)| c)
+    }
+
+    // The position of a location in a dense grid, if it is covered.
+    fn index(&self, l: &Location) -> Option<usize> {
+        match &self.0 {
+            Cells::Dense { origin, width, height, .. } => {
+                let x = l.x - origin.x;
+                let y = l.y - origin.y;
+                if x >= 0 && y >= 0 && (x as usize) < *width && (y as usize) < *height {
+                    Some(y as usize * width + x as usize)
+                } else {
+                    None
+                }
+            }
+            Cells::Sparse(_) => None,
         }
     }
 }
 
-#[derive(Clone, Copy, Debug, Hash, PartialOrd, Ord, PartialEq, Eq)]
-pub struct Location {
-    pub x: i64, // steps right from the reference point
-    pub y: i64, // steps down from the reference point
+// The smallest rectangle containing all the cells set.
+#[derive(Clone, Copy, Debug)]
+struct Bounds {
+    x_min: i64,
+    x_max: i64,
+    y_min: i64,
+    y_max: i64,
 }
 
-impl Location {
-    pub fn go(&self, direction: Direction) -> Location {
-        match direction {
-            Direction::Up => Location{ x: self.x, y: self.y - 1 },
-            Direction::Down => Location{ x: self.x, y: self.y + 1 },
-            Direction::Left => Location{ x: self.x - 1, y: self.y },
-            Direction::Right => Location{ x: self.x + 1, y: self.y },
+impl Bounds {
+    fn of(l: Location) -> Bounds {
+        Bounds { x_min: l.x, x_max: l.x, y_min: l.y, y_max: l.y }
+    }
+
+    fn extend(self, l: Location) -> Bounds {
+        Bounds {
+            x_min: self.x_min.min(l.x),
+            x_max: self.x_max.max(l.x),
+            y_min: self.y_min.min(l.y),
+            y_max: self.y_max.max(l.y),
         }
     }
+
+    fn on_border(&self, l: &Location) -> bool {
+        l.x == self.x_min || l.x == self.x_max || l.y == self.y_min || l.y == self.y_max
+    }
+}
+
+// Converts the cells of a grid to and from characters, to read a grid
+// from text and to display it.
+pub trait Glyph: Sized {
+    fn glyph(&self) -> char;
+
+    // None leaves the cell unset.
+    fn from_glyph(c: char) -> Option<Self>;
+}
+
+impl Glyph for char {
+    fn glyph(&self) -> char {
+        *self
+    }
+
+    fn from_glyph(c: char) -> Option<char> {
+        Some(c)
+    }
 }
 
-#[derive(Default)]
-pub struct Grid {
-    pub symbols: HashMap<Location, char>,
+#[derive(Clone, Debug)]
+pub struct Grid<T = char> {
+    pub symbols: Symbols<T>,
+    bounds: Cell<Option<Bounds>>, // cached, None when unknown or empty
 }
 
-impl Grid {
-    pub fn new() -> Grid {
-        Grid { symbols: HashMap::new() }
+impl<T> Default for Grid<T> {
+    fn default() -> Grid<T> {
+        Grid { symbols: Symbols::default(), bounds: Cell::new(None) }
     }
-    pub fn create_from(s: &str) -> Grid {
-        let mut symbols = HashMap::new();
+}
+
+impl<T: Glyph> Grid<T> {
+    // Reads a map, one line per row, converting the characters with
+    // `Glyph::from_glyph`.
+    pub fn create_from(s: &str) -> Grid<T> {
+        Grid::create_with(s, T::from_glyph)
+    }
+}
+
+impl<T: PartialEq> Grid<T> {
+    pub fn find( &self, c: T ) -> Option<&Location> {
+        self.symbols.iter().find(|(_, sym)| **sym == c ).map(|(loc, _)| loc)
+    }
+}
+
+impl<T> Grid<T> {
+    // The rows of the grid within its bounds, from the top, with the
+    // cells from the left.
+    pub fn iter_rows(&self) -> impl Iterator<Item = Vec<Option<&T>>> + '_ {
+        let (x0, x1) = (self.x_min(), self.x_max());
+        // no rows at all when empty
+        let (y0, y1) = if self.is_empty() { (1, 0) } else { (self.y_min(), self.y_max()) };
+        (y0..=y1).map(move |y| (x0..=x1).map(|x| self.get(&Location { x, y })).collect())
+    }
+
+    // The locations of the cells satisfying `predicate`, in no particular
+    // order.
+    pub fn cells_matching<'a>(&'a self, predicate: impl Fn(&T) -> bool + 'a) -> impl Iterator<Item = Location> + 'a {
+        self.iter().filter(move |(_, c)| predicate(c)).map(|(l, _)| l)
+    }
+
+    // The region of the cells reachable from `start` going up, down, left
+    // and right through the cells satisfying `passable`. The region is
+    // empty if `start` itself is not passable.
+    pub fn flood_fill(&self, start: Location, passable: impl Fn(&T) -> bool) -> HashSet<Location> {
+        let mut region = HashSet::new();
+        let mut stack = vec![start];
+        while let Some(l) = stack.pop() {
+            if self.get(&l).is_some_and(&passable) && region.insert(l) {
+                stack.extend_from_slice(&l.neighbors4());
+            }
+        }
+        region
+    }
+
+    // An empty grid, that can grow in any direction.
+    pub fn new() -> Grid<T> {
+        Grid::default()
+    }
+
+    // An empty grid stored densely, for maps with most of the cells in
+    // the given ranges set. It still grows if needed.
+    pub fn dense(xs: RangeInclusive<i64>, ys: RangeInclusive<i64>) -> Grid<T> {
+        let width = (xs.end() - xs.start() + 1).max(0) as usize;
+        let height = (ys.end() - ys.start() + 1).max(0) as usize;
+        Grid {
+            symbols: Symbols(Cells::Dense {
+                origin: Location { x: *xs.start(), y: *ys.start() },
+                width,
+                height,
+                cells: (0..width * height).map(|_| None).collect(),
+                len: 0,
+            }),
+            bounds: Cell::new(None),
+        }
+    }
+
+    // Reads a map, one line per row, converting the characters with
+    // `convert`. Whitespace is left unset. The map is stored densely if
+    // most of its rectangle is set.
+    pub fn create_with(s: &str, convert: impl Fn(char) -> Option<T>) -> Grid<T> {
+        let mut symbols = Vec::new();
         let mut x = 0;
         let mut y = 0;
         for c in s.chars() {
             match c {
                 '\n' => { y += 1; x = 0; },
                 c => {
-                    if !c.is_ascii_whitespace() {
-                        symbols.insert(Location { x, y }, c);
+                    if let Some(v) = Some(c).filter(|c| !c.is_ascii_whitespace()).and_then(&convert) {
+                        symbols.push((Location { x, y }, v));
                     }
                     x += 1;
                 }
             }
         }
-        Grid { symbols }
+        let bounds = match symbols.split_first() {
+            Some((first, rest)) => rest.iter().fold(Bounds::of(first.0), |b, (l, _)| b.extend(*l)),
+            None => return Grid::new(),
+        };
+        let area = (bounds.x_max - bounds.x_min + 1) * (bounds.y_max - bounds.y_min + 1);
+        let mut g = if 2 * symbols.len() as i64 >= area {
+            Grid::dense(bounds.x_min..=bounds.x_max, bounds.y_min..=bounds.y_max)
+        } else {
+            Grid::new()
+        };
+        for (l, c) in symbols {
+            g.insert(l, c);
+        }
+        g
+    }
+
+    pub fn is_dense(&self) -> bool {
+        matches!(self.symbols.0, Cells::Dense { .. })
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        if self.bounds.get().is_none() {
+            let mut it = self.iter().map(|(l, _)| l);
+            let b = it.next().map(|first| it.fold(Bounds::of(first), Bounds::extend));
+            self.bounds.set(b);
+        }
+        self.bounds.get()
     }
+
     pub fn x_min( &self ) -> i64 {
-        *self.symbols.keys().map(|Location { x, .. }| x).min().unwrap_or(&0)
+        self.bounds().map_or(0, |b| b.x_min)
     }
     pub fn x_max( &self ) -> i64 {
-        *self.symbols.keys().map(|Location { x, .. }| x).max().unwrap_or(&0)
+        self.bounds().map_or(0, |b| b.x_max)
     }
     pub fn y_min( &self ) -> i64 {
-        *self.symbols.keys().map(|Location { y, .. }| y).min().unwrap_or(&0)
+        self.bounds().map_or(0, |b| b.y_min)
     }
     pub fn y_max( &self ) -> i64 {
-        *self.symbols.keys().map(|Location { y, .. }| y).max().unwrap_or(&0)
+        self.bounds().map_or(0, |b| b.y_max)
     }
-    pub fn get(&self, l: &Location) -> Option<&char> {
+
+    pub fn get(&self, l: &Location) -> Option<&T> {
         self.symbols.get(l)
     }
-    pub fn insert(&mut self, l: Location, c: char) -> Option<char> {
-        self.symbols.insert(l, c)
+
+    pub fn insert(&mut self, l: Location, c: T) -> Option<T> {
+        if self.symbols.index(&l).is_none() {
+            self.grow(l);
+        }
+        let previous = match self.symbols.index(&l) {
+            Some(i) => match &mut self.symbols.0 {
+                Cells::Dense { cells, len, .. } => {
+                    let previous = cells[i].replace((l, c)).map(|(_, c)| c);
+                    if previous.is_none() {
+                        *len += 1;
+                    }
+                    previous
+                }
+                Cells::Sparse(_) => unreachable!(),
+            },
+            None => match &mut self.symbols.0 {
+                Cells::Sparse(symbols) => symbols.insert(l, c),
+                Cells::Dense { .. } => unreachable!(),
+            },
+        };
+        if previous.is_none() {
+            // the cached bounds can only grow, unless the grid was empty
+            if let Some(b) = self.bounds.get() {
+                self.bounds.set(Some(b.extend(l)));
+            }
+        }
+        previous
     }
-    pub fn remove(&mut self, l: &Location) -> Option<char> {
-        self.symbols.remove(l)
+
+    pub fn remove(&mut self, l: &Location) -> Option<T> {
+        let removed = match self.symbols.index(l) {
+            Some(i) => match &mut self.symbols.0 {
+                Cells::Dense { cells, len, .. } => {
+                    let removed = cells[i].take().map(|(_, c)| c);
+                    if removed.is_some() {
+                        *len -= 1;
+                    }
+                    removed
+                }
+                Cells::Sparse(_) => unreachable!(),
+            },
+            None => match &mut self.symbols.0 {
+                Cells::Sparse(symbols) => symbols.remove(l),
+                Cells::Dense { .. } => None,
+            },
+        };
+        if removed.is_some() && self.bounds.get().is_some_and(|b| b.on_border(l)) {
+            self.bounds.set(None);
+        }
+        removed
     }
-    pub fn find( &self, c: char ) -> Option<&Location> {
-        self.symbols.iter().find(|(_, sym)| **sym == c ).map(|(loc, _)| loc)
+
+    // Iterates over the cells set, in no particular order, with the
+    // locations by value.
+    pub fn iter(&self) -> impl Iterator<Item = (Location, &T)> + '_ {
+        self.symbols.iter().map(|(l, c)| (*l, c))
+    }
+
+    // Extends a dense grid to cover a location, doubling its size in the
+    // directions needed.
+    fn grow(&mut self, l: Location) {
+        if let Cells::Dense { origin, width, height, .. } = &self.symbols.0 {
+            let (w, h) = (*width as i64, *height as i64);
+            let x_min = if l.x < origin.x { l.x.min(origin.x - w) } else { origin.x };
+            let y_min = if l.y < origin.y { l.y.min(origin.y - h) } else { origin.y };
+            let x_max = if l.x >= origin.x + w { l.x.max(origin.x + 2 * w - 1) } else { origin.x + w - 1 };
+            let y_max = if l.y >= origin.y + h { l.y.max(origin.y + 2 * h - 1) } else { origin.y + h - 1 };
+            let mut g = Grid::dense(x_min..=x_max, y_min..=y_max);
+            let bounds = self.bounds.get();
+            let locations: Vec<_> = self.iter().map(|(l, _)| l).collect();
+            for l in locations {
+                let c = self.remove(&l).unwrap();
+                g.insert(l, c);
+            }
+            g.bounds.set(bounds);
+            *self = g;
+        }
     }
 }
 
-impl Display for Grid {
+impl<T: Glyph> Display for Grid<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let x0 = self.x_min();
-        let x1 = self.x_max();
-        let y0 = self.y_min();
-        let y1 = self.y_max();
-
-        for y in y0..=y1 {
-            for x in x0..=x1 {
-                write!(f, "{}", self.get(&Location { x, y }).unwrap_or(&' '))?;
+        for row in self.iter_rows() {
+            for c in row {
+                write!(f, "{}", c.map_or(' ', Glyph::glyph))?;
             }
             writeln!(f)?;
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn storage() {
+        let map = "#.#\n.@.\n#.#\n";
+        assert!(Grid::<char>::create_from(map).is_dense());
+        assert!(!Grid::<char>::create_from("#   \n    \n   #\n").is_dense());
+
+        // both storages behave the same
+        for g in [Grid::new(), Grid::dense(0..=2, 0..=2)].iter_mut() {
+            for (l, c) in Grid::<char>::create_from(map).iter() {
+                g.insert(l, *c);
+            }
+            assert_eq!(g.to_string(), map);
+            assert_eq!(g.find('@'), Some(&Location { x: 1, y: 1 }));
+            assert_eq!(g.symbols.len(), 9);
+            assert_eq!(g.symbols.values().filter(|&c| c == &'#').count(), 4);
+            assert_eq!(g.insert(Location { x: 1, y: 1 }, '.'), Some('@'));
+            assert_eq!(g.remove(&Location { x: 1, y: 1 }), Some('.'));
+            assert_eq!(g.remove(&Location { x: 1, y: 1 }), None);
+            assert_eq!(g.len(), 8);
+            assert_eq!(g.to_string(), "#.#\n. .\n#.#\n");
+        }
+    }
+
+    #[test]
+    fn bounds() {
+        let mut g = Grid::<char>::create_from("..\n..\n");
+        assert_eq!((g.x_min(), g.x_max(), g.y_min(), g.y_max()), (0, 1, 0, 1));
+
+        // dense grids grow as needed
+        g.insert(Location { x: -3, y: 5 }, '#');
+        assert!(g.is_dense());
+        assert_eq!((g.x_min(), g.x_max(), g.y_min(), g.y_max()), (-3, 1, 0, 5));
+        assert_eq!(g.get(&Location { x: -3, y: 5 }), Some(&'#'));
+        assert_eq!(g.get(&Location { x: 1, y: 1 }), Some(&'.'));
+
+        // and shrink when the cells on the border are removed
+        g.remove(&Location { x: -3, y: 5 });
+        assert_eq!((g.x_min(), g.x_max(), g.y_min(), g.y_max()), (0, 1, 0, 1));
+        for l in [(0, 0), (1, 0), (0, 1), (1, 1)].iter().map(|&(x, y)| Location { x, y }) {
+            g.remove(&l);
+        }
+        assert!(g.is_empty());
+        assert_eq!((g.x_min(), g.x_max(), g.y_min(), g.y_max()), (0, 0, 0, 0));
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Tile {
+        Wall,
+        Open,
+    }
+
+    impl Glyph for Tile {
+        fn glyph(&self) -> char {
+            match self {
+                Tile::Wall => '#',
+                Tile::Open => '.',
+            }
+        }
+
+        fn from_glyph(c: char) -> Option<Tile> {
+            match c {
+                '#' => Some(Tile::Wall),
+                '.' => Some(Tile::Open),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn cells() {
+        let g: Grid<Tile> = Grid::create_from("#.#\n.x.\n");
+        assert_eq!(g.get(&Location { x: 0, y: 0 }), Some(&Tile::Wall));
+        assert_eq!(g.get(&Location { x: 1, y: 1 }), None);
+        assert_eq!(g.find(Tile::Open), Some(&Location { x: 1, y: 0 }));
+        assert_eq!(g.to_string(), "#.#\n. .\n");
+
+        // cells without glyphs, like distances
+        let mut d = Grid::new();
+        for (l, _) in g.iter().filter(|(_, &t)| t == Tile::Open) {
+            d.insert(l, l.x + l.y);
+        }
+        assert_eq!(d.len(), 3);
+        assert_eq!(d.get(&Location { x: 2, y: 1 }), Some(&3));
+    }
+
+    #[test]
+    fn neighbors() {
+        let l = Location { x: 2, y: -1 };
+        assert_eq!(l.neighbors4().iter().map(|n| l.manhattan(n)).collect::<Vec<_>>(), vec![1; 4]);
+        assert_eq!(l.neighbors8().iter().map(|n| l.chebyshev(n)).collect::<Vec<_>>(), vec![1; 8]);
+        assert_eq!(l.neighbors8().iter().filter(|n| l.manhattan(n) == 2).count(), 4);
+        assert_eq!(l.manhattan(&Location { x: -1, y: 3 }), 7);
+        assert_eq!(l.chebyshev(&Location { x: -1, y: 3 }), 4);
+    }
+
+    #[test]
+    fn regions() {
+        let g = Grid::<char>::create_from("..#.\n.##.\n#...\n");
+        let rows: Vec<String> = g.iter_rows().map(|r| r.iter().map(|c| *c.unwrap()).collect()).collect();
+        assert_eq!(rows, vec!["..#.", ".##.", "#..."]);
+        assert_eq!(g.cells_matching(|&c| c == '#').count(), 4);
+
+        let left = g.flood_fill(Location { x: 0, y: 0 }, |&c| c == '.');
+        assert_eq!(left.len(), 3);
+        let right = g.flood_fill(Location { x: 3, y: 0 }, |&c| c == '.');
+        assert_eq!(right.len(), 5);
+        assert!(g.flood_fill(Location { x: 2, y: 0 }, |&c| c == '.').is_empty());
+        assert_eq!(Grid::<char>::new().iter_rows().count(), 0);
+    }
+}
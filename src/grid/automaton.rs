@@ -0,0 +1,277 @@
+// Cellular automata: a set of live cells evolving one generation at a
+// time. The topology gives the neighbours of each cell (on a bounded,
+// toroidal or infinite plane, or on the nested levels of a recursive
+// space), and the rule gives the next state of a cell from its state
+// and its number of live neighbours.
+use super::{Glyph, Grid, Layered, Location};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+pub enum Neighborhood {
+    VonNeumann,             // up, down, left and right
+    Moore,                  // the eight cells around
+    Offsets(Vec<(i64, i64)>), // any other
+}
+
+impl Neighborhood {
+    fn around(&self, l: &Location) -> Vec<Location> {
+        match self {
+            Neighborhood::VonNeumann => l.neighbors4().to_vec(),
+            Neighborhood::Moore => l.neighbors8().to_vec(),
+            Neighborhood::Offsets(offsets) => {
+                offsets.iter().map(|(dx, dy)| Location { x: l.x + dx, y: l.y + dy }).collect()
+            }
+        }
+    }
+}
+
+pub trait Topology {
+    type Cell: Copy + Eq + Hash + Ord;
+
+    fn neighbors(&self, cell: &Self::Cell) -> Vec<Self::Cell>;
+}
+
+// The whole plane.
+pub struct Infinite(pub Neighborhood);
+
+impl Topology for Infinite {
+    type Cell = Location;
+
+    fn neighbors(&self, cell: &Location) -> Vec<Location> {
+        self.0.around(cell)
+    }
+}
+
+// A rectangle from (0, 0), with nothing beyond the borders.
+pub struct Bounded {
+    pub width: i64,
+    pub height: i64,
+    pub neighborhood: Neighborhood,
+}
+
+impl Topology for Bounded {
+    type Cell = Location;
+
+    fn neighbors(&self, cell: &Location) -> Vec<Location> {
+        let mut v = self.neighborhood.around(cell);
+        v.retain(|l| (0..self.width).contains(&l.x) && (0..self.height).contains(&l.y));
+        v
+    }
+}
+
+// A rectangle from (0, 0), with the opposite borders joined.
+pub struct Toroidal {
+    pub width: i64,
+    pub height: i64,
+    pub neighborhood: Neighborhood,
+}
+
+impl Topology for Toroidal {
+    type Cell = Location;
+
+    fn neighbors(&self, cell: &Location) -> Vec<Location> {
+        self.neighborhood
+            .around(cell)
+            .into_iter()
+            .map(|l| Location { x: l.x.rem_euclid(self.width), y: l.y.rem_euclid(self.height) })
+            .collect()
+    }
+}
+
+// Square levels of odd `size`, where the middle cell of each level holds
+// the next level (one layer deeper), and the level is itself the middle
+// cell of the one above. The neighbours are up, down, left and right.
+pub struct Recursive {
+    pub size: i64,
+}
+
+impl Recursive {
+    pub fn middle(&self) -> Location {
+        Location { x: self.size / 2, y: self.size / 2 }
+    }
+}
+
+impl Topology for Recursive {
+    type Cell = Layered<Location>;
+
+    fn neighbors(&self, cell: &Layered<Location>) -> Vec<Layered<Location>> {
+        let (middle, last) = (self.middle(), self.size - 1);
+        let mut v = Vec::new();
+        for n in cell.point.neighbors4().iter() {
+            let (dx, dy) = (n.x - cell.point.x, n.y - cell.point.y);
+            if *n == middle {
+                // the whole border of the level below facing this cell
+                for i in 0..self.size {
+                    let x = match dx { 1 => 0, -1 => last, _ => i };
+                    let y = match dy { 1 => 0, -1 => last, _ => i };
+                    v.push(cell.deeper(Location { x, y }));
+                }
+            } else if !(0..self.size).contains(&n.x) || !(0..self.size).contains(&n.y) {
+                // the cell next to the middle of the level above
+                v.push(cell.shallower(Location { x: middle.x + dx, y: middle.y + dy }));
+            } else {
+                v.push(cell.at(*n));
+            }
+        }
+        v
+    }
+}
+
+// The next state of a cell, from its state and its number of live
+// neighbours.
+pub struct Rule(Box<dyn Fn(bool, usize) -> bool>);
+
+impl Rule {
+    pub fn new(f: impl Fn(bool, usize) -> bool + 'static) -> Rule {
+        Rule(Box::new(f))
+    }
+
+    // Dead cells come alive with a number of neighbours in `birth`, live
+    // cells stay alive with a number in `survival`.
+    pub fn birth_survival(birth: &[usize], survival: &[usize]) -> Rule {
+        let (birth, survival) = (birth.to_vec(), survival.to_vec());
+        Rule::new(move |alive, n| if alive { survival.contains(&n) } else { birth.contains(&n) })
+    }
+
+    pub fn next(&self, alive: bool, neighbors: usize) -> bool {
+        (self.0)(alive, neighbors)
+    }
+}
+
+pub struct Automaton<T: Topology> {
+    pub topology: T,
+    rule: Rule,
+    live: HashSet<T::Cell>,
+    generation: usize,
+}
+
+impl<T: Topology> Automaton<T> {
+    pub fn new(topology: T, rule: Rule, live: impl IntoIterator<Item = T::Cell>) -> Automaton<T> {
+        Automaton {
+            topology,
+            rule,
+            live: live.into_iter().collect(),
+            generation: 0,
+        }
+    }
+
+    pub fn is_alive(&self, cell: &T::Cell) -> bool {
+        self.live.contains(cell)
+    }
+
+    pub fn live(&self) -> &HashSet<T::Cell> {
+        &self.live
+    }
+
+    pub fn generation(&self) -> usize {
+        self.generation
+    }
+
+    pub fn step(&mut self) {
+        // count the live neighbours of the cells that may change
+        let mut count: HashMap<T::Cell, usize> = self.live.iter().map(|c| (*c, 0)).collect();
+        for cell in self.live.iter() {
+            for n in self.topology.neighbors(cell) {
+                *count.entry(n).or_insert(0) += 1;
+            }
+        }
+        let rule = &self.rule;
+        let live = &self.live;
+        self.live = count.into_iter().filter(|(c, n)| rule.next(live.contains(c), *n)).map(|(c, _)| c).collect();
+        self.generation += 1;
+    }
+
+    pub fn run(&mut self, generations: usize) {
+        for _ in 0..generations {
+            self.step();
+        }
+    }
+
+    // Steps until a state repeats, and returns the generation where the
+    // cycle starts and its length. The automaton is left at the first
+    // repeated state.
+    pub fn find_cycle(&mut self) -> (usize, usize) {
+        let mut seen = HashMap::new();
+        loop {
+            let mut state: Vec<T::Cell> = self.live.iter().copied().collect();
+            state.sort_unstable();
+            if let Some(start) = seen.insert(state, self.generation) {
+                return (start, self.generation - start);
+            }
+            self.step();
+        }
+    }
+}
+
+impl<T: Topology<Cell = Location>> Automaton<T> {
+    // An automaton with the cells of a map satisfying `alive` as the
+    // live cells.
+    pub fn from_grid<G>(topology: T, rule: Rule, grid: &Grid<G>, alive: impl Fn(&G) -> bool) -> Automaton<T> {
+        Automaton::new(topology, rule, grid.cells_matching(alive))
+    }
+
+    // The live cells as a map.
+    pub fn to_grid<G: Glyph + Clone>(&self, glyph: G) -> Grid<G> {
+        let mut g = Grid::new();
+        for l in self.live.iter() {
+            g.insert(*l, glyph.clone());
+        }
+        g
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn life(topology: Bounded, map: &str) -> Automaton<Bounded> {
+        let g: Grid = Grid::create_from(map);
+        Automaton::from_grid(topology, Rule::birth_survival(&[3], &[2, 3]), &g, |&c| c == '#')
+    }
+
+    #[test]
+    fn blinker() {
+        let mut a = life(Bounded { width: 5, height: 5, neighborhood: Neighborhood::Moore }, ".....\n..#..\n..#..\n..#..\n");
+        a.step();
+        assert_eq!(a.to_grid('#').to_string(), "###\n");
+        assert_eq!(a.find_cycle(), (1, 2));
+        assert_eq!(a.generation(), 3);
+    }
+
+    #[test]
+    fn topologies() {
+        // a glider goes around a torus, and dies in a box
+        let glider = ".#...\n..#..\n###..\n.....\n.....\n";
+        let mut a = life(Bounded { width: 5, height: 5, neighborhood: Neighborhood::Moore }, glider);
+        a.run(20);
+        assert_eq!(a.live().len(), 4); // a block in the corner
+        let g: Grid = Grid::create_from(glider);
+        let torus = Toroidal { width: 5, height: 5, neighborhood: Neighborhood::Moore };
+        let mut t = Automaton::from_grid(torus, Rule::birth_survival(&[3], &[2, 3]), &g, |&c| c == '#');
+        assert_eq!(t.find_cycle(), (0, 20));
+
+        // a single cell on the plane spreads in a diamond
+        let mut i = Automaton::new(Infinite(Neighborhood::VonNeumann), Rule::new(|alive, n| alive || n > 0), vec![
+            Location { x: 0, y: 0 },
+        ]);
+        i.run(3);
+        assert_eq!(i.live().len(), 25);
+        assert!(i.live().iter().all(|l| l.manhattan(&Location { x: 0, y: 0 }) <= 3));
+    }
+
+    #[test]
+    fn recursive() {
+        let r = Recursive { size: 5 };
+        let n = |layer, x, y| {
+            let mut v = r.neighbors(&Layered::new(layer, Location { x, y }));
+            v.sort();
+            v
+        };
+        let at = |layer, x, y| Layered::new(layer, Location { x, y });
+        // the examples of day 24
+        assert_eq!(n(1, 3, 3), vec![at(1, 2, 3), at(1, 3, 2), at(1, 3, 4), at(1, 4, 3)]);
+        assert_eq!(n(1, 4, 0), vec![at(0, 2, 1), at(0, 3, 2), at(1, 3, 0), at(1, 4, 1)]);
+        assert_eq!(n(0, 3, 2).len(), 8);
+        assert!(n(0, 3, 2).contains(&at(1, 4, 4)));
+    }
+}
@@ -0,0 +1,301 @@
+// Small automata (at most 32 cells) as the bits of a u32, with the cell
+// (x, y) on bit W * y + x. A generation is computed for all the cells at
+// once, with a few shifts and masks, instead of one cell at a time.
+use super::{Grid, Location};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+
+#[derive(Clone, Copy, Debug, Default, Hash, PartialEq, Eq)]
+pub struct Bitboard<const W: usize, const H: usize>(pub u32);
+
+impl<const W: usize, const H: usize> Bitboard<W, H> {
+    pub const EMPTY: Bitboard<W, H> = Bitboard(0);
+    pub const FULL: Bitboard<W, H> = Bitboard(if W * H == 32 { !0 } else { (1 << (W * H)) - 1 });
+
+    pub fn cell(x: usize, y: usize) -> Bitboard<W, H> {
+        const { assert!(W * H <= 32) };
+        Bitboard(1 << (W * y + x))
+    }
+
+    pub fn row(y: usize) -> Bitboard<W, H> {
+        Bitboard(((1 << W) - 1) << (W * y))
+    }
+
+    pub fn column(x: usize) -> Bitboard<W, H> {
+        Bitboard((0..H).fold(0, |b, y| b | Bitboard::<W, H>::cell(x, y).0))
+    }
+
+    // The cells of a map satisfying `alive`, from (0, 0).
+    pub fn from_grid<T>(grid: &Grid<T>, alive: impl Fn(&T) -> bool) -> Bitboard<W, H> {
+        let mut b = Bitboard::EMPTY;
+        for l in grid.cells_matching(alive) {
+            b.set(l, true);
+        }
+        b
+    }
+
+    fn inside(l: Location) -> bool {
+        (0..W as i64).contains(&l.x) && (0..H as i64).contains(&l.y)
+    }
+
+    // Always false outside the board.
+    pub fn is_alive(&self, l: Location) -> bool {
+        Self::inside(l) && self.contains(Self::cell(l.x as usize, l.y as usize))
+    }
+
+    // Does nothing outside the board.
+    pub fn set(&mut self, l: Location, alive: bool) {
+        if !Self::inside(l) {
+            return;
+        }
+        let c = Self::cell(l.x as usize, l.y as usize).0;
+        if alive {
+            self.0 |= c;
+        } else {
+            self.0 &= !c;
+        }
+    }
+
+    // Whether any of the cells of `mask` is alive.
+    pub fn contains(&self, mask: Bitboard<W, H>) -> bool {
+        self.0 & mask.0 != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // The state of the neighbour one step away, on each cell: the cell
+    // (x, y) of the result holds the cell (x - dx, y - dy) of this one.
+    pub fn shift(&self, dx: i64, dy: i64) -> Bitboard<W, H> {
+        let mut b = self.0;
+        match dx {
+            1 => b = (b << 1) & !Bitboard::<W, H>::column(0).0,
+            -1 => b = (b >> 1) & !Bitboard::<W, H>::column(W - 1).0,
+            _ => {}
+        }
+        match dy {
+            1 => b <<= W,
+            -1 => b >>= W,
+            _ => {}
+        }
+        Bitboard(b & Bitboard::<W, H>::FULL.0)
+    }
+
+    // The neighbours up, down, left and right.
+    pub fn von_neumann(&self) -> [Bitboard<W, H>; 4] {
+        [self.shift(0, 1), self.shift(0, -1), self.shift(1, 0), self.shift(-1, 0)]
+    }
+
+    // The eight neighbours around.
+    pub fn moore(&self) -> [Bitboard<W, H>; 8] {
+        let [u, d, l, r] = self.von_neumann();
+        [u, d, l, r, u.shift(1, 0), u.shift(-1, 0), d.shift(1, 0), d.shift(-1, 0)]
+    }
+
+    // The next generation, given the neighbours of each cell (as from
+    // `von_neumann`), with dead cells coming alive with a number of
+    // live neighbours in `birth`, and live cells staying alive with a
+    // number in `survival`.
+    pub fn step(&self, neighbors: &[Bitboard<W, H>], birth: &[usize], survival: &[usize]) -> Bitboard<W, H> {
+        let mut count = Count::default();
+        neighbors.iter().for_each(|n| count.add(n.0));
+        Bitboard(count.apply(self.0, birth, survival) & Bitboard::<W, H>::FULL.0)
+    }
+}
+
+// The number of live neighbours of each cell, counted in parallel:
+// `at_least[k]` has the bits of the cells with more than k neighbours.
+#[derive(Default)]
+struct Count {
+    at_least: [u32; 9],
+}
+
+impl Count {
+    fn add(&mut self, neighbors: u32) {
+        for k in (1..self.at_least.len()).rev() {
+            self.at_least[k] |= self.at_least[k - 1] & neighbors;
+        }
+        self.at_least[0] |= neighbors;
+    }
+
+    // the cells with exactly n neighbours
+    fn exactly(&self, n: usize) -> u32 {
+        match n {
+            0 => !self.at_least[0],
+            _ => self.at_least[n - 1] & !self.at_least.get(n).unwrap_or(&0),
+        }
+    }
+
+    fn apply(&self, alive: u32, birth: &[usize], survival: &[usize]) -> u32 {
+        let born = birth.iter().fold(0, |b, &n| b | self.exactly(n));
+        let survive = survival.iter().fold(0, |b, &n| b | self.exactly(n));
+        (alive & survive) | (!alive & born)
+    }
+}
+
+// The levels of a recursive space, where the middle cell of each level
+// holds the next level (one layer deeper), as a bitboard per level.
+// Only the levels between the outermost and the innermost live cells are
+// stored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Levels<const W: usize, const H: usize> {
+    top: i64,
+    levels: VecDeque<Bitboard<W, H>>,
+}
+
+impl<const W: usize, const H: usize> Levels<W, H> {
+    // The space with `level` on layer 0.
+    pub fn new(level: Bitboard<W, H>) -> Levels<W, H> {
+        let mut levels = VecDeque::new();
+        levels.push_back(Bitboard(level.0 & !Levels::<W, H>::middle().0));
+        Levels { top: 0, levels }
+    }
+
+    fn middle() -> Bitboard<W, H> {
+        Bitboard::cell(W / 2, H / 2)
+    }
+
+    pub fn level(&self, layer: i64) -> Bitboard<W, H> {
+        usize::try_from(layer - self.top).ok().and_then(|i| self.levels.get(i)).copied().unwrap_or_default()
+    }
+
+    // The layers holding live cells.
+    pub fn layers(&self) -> std::ops::Range<i64> {
+        self.top..self.top + self.levels.len() as i64
+    }
+
+    pub fn len(&self) -> usize {
+        self.levels.iter().map(|b| b.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.levels.iter().all(|b| b.is_empty())
+    }
+
+    // The next generation, with neighbours up, down, left and right, and
+    // the same rules as `Bitboard::step`.
+    pub fn step(&self, birth: &[usize], survival: &[usize]) -> Levels<W, H> {
+        let (mx, my) = (W / 2, H / 2);
+        let cell = Bitboard::<W, H>::cell;
+        // the cells around the middle, with the border of the inner level
+        // and the border of the outer level facing them
+        let sides = [
+            (cell(mx, my - 1), Bitboard::<W, H>::row(0)),
+            (cell(mx, my + 1), Bitboard::<W, H>::row(H - 1)),
+            (cell(mx - 1, my), Bitboard::<W, H>::column(0)),
+            (cell(mx + 1, my), Bitboard::<W, H>::column(W - 1)),
+        ];
+
+        let mut next = VecDeque::new();
+        for layer in self.top - 1..=self.top + self.levels.len() as i64 {
+            let (outer, level, inner) = (self.level(layer - 1), self.level(layer), self.level(layer + 1));
+            let mut count = Count::default();
+            level.von_neumann().iter().for_each(|n| count.add(n.0));
+            for &(side, border) in sides.iter() {
+                if outer.contains(side) {
+                    count.add(border.0);
+                }
+                for _ in 0..(inner.0 & border.0).count_ones() {
+                    count.add(side.0);
+                }
+            }
+            let b = count.apply(level.0, birth, survival);
+            next.push_back(Bitboard(b & Bitboard::<W, H>::FULL.0 & !Levels::<W, H>::middle().0));
+        }
+
+        let mut top = self.top - 1;
+        while next.len() > 1 && next.front().is_some_and(|b| b.is_empty()) {
+            next.pop_front();
+            top += 1;
+        }
+        while next.len() > 1 && next.back().is_some_and(|b| b.is_empty()) {
+            next.pop_back();
+        }
+        Levels { top, levels: next }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::automaton::{Automaton, Bounded, Neighborhood, Recursive, Rule};
+    use crate::grid::Layered;
+
+    type Board = Bitboard<5, 5>;
+
+    #[test]
+    fn masks() {
+        assert_eq!(Board::FULL.0, (1 << 25) - 1);
+        assert_eq!(Bitboard::<8, 4>::FULL.0, !0);
+        assert_eq!(Board::row(1).0, 0b11111 << 5);
+        assert_eq!(Board::column(4).len(), 5);
+        let b = Board::cell(0, 0);
+        assert_eq!(b.shift(1, 0), Board::cell(1, 0));
+        assert_eq!(b.shift(-1, 0), Board::EMPTY);
+        assert_eq!(Board::cell(4, 0).shift(1, 0), Board::EMPTY);
+        assert_eq!(b.shift(0, 1), Board::cell(0, 1));
+        assert_eq!(Board::cell(0, 4).shift(0, 1), Board::EMPTY);
+        assert_eq!(Board::cell(2, 2).moore().iter().fold(0, |b, n| b | n.0).count_ones(), 8);
+
+        // nothing outside the board
+        let mut b = Board::FULL;
+        for &(x, y) in [(-1, 0), (5, 0), (0, 5), (0, -1), (1, 7)].iter() {
+            let l = Location { x, y };
+            assert!(!b.is_alive(l));
+            b.set(l, true);
+            b.set(l, false);
+        }
+        assert_eq!(b, Board::FULL);
+    }
+
+    #[test]
+    fn blinker() {
+        let g: Grid = Grid::create_from(".....\n..#..\n..#..\n..#..\n");
+        let b = Board::from_grid(&g, |&c| c == '#');
+        let life = |b: Board| b.step(&b.moore(), &[3], &[2, 3]);
+        assert_eq!(life(b), Bitboard(Board::row(2).0 & !Board::column(0).0 & !Board::column(4).0));
+        assert_eq!(life(life(b)), b);
+    }
+
+    #[test]
+    fn recursive() {
+        // the same evolution as the generic automaton
+        let g: Grid = Grid::create_from("....#\n#..#.\n#.?##\n..#..\n#....");
+        let mut levels = Levels::new(Board::from_grid(&g, |&c| c == '#'));
+        let live = g.cells_matching(|&c| c == '#').map(|l| Layered::new(0, l));
+        let mut a = Automaton::new(Recursive { size: 5 }, Rule::birth_survival(&[1, 2], &[1]), live);
+        for _ in 0..10 {
+            levels = levels.step(&[1, 2], &[1]);
+            a.step();
+            assert_eq!(levels.len(), a.live().len());
+            for layer in levels.layers() {
+                for (y, x) in (0..5).flat_map(|y| (0..5).map(move |x| (y, x))) {
+                    let l = Location { x, y };
+                    assert_eq!(levels.level(layer).is_alive(l), a.is_alive(&Layered::new(layer, l)));
+                }
+            }
+        }
+        assert_eq!(levels.layers(), -5..6);
+        assert_eq!(levels.len(), 99);
+    }
+
+    #[test]
+    fn bounded() {
+        // the same evolution as the generic automaton
+        let g: Grid = Grid::create_from("....#\n#..#.\n#..##\n..#..\n#....");
+        let mut b = Board::from_grid(&g, |&c| c == '#');
+        let area = Bounded { width: 5, height: 5, neighborhood: Neighborhood::VonNeumann };
+        let mut a = Automaton::from_grid(area, Rule::birth_survival(&[1, 2], &[1]), &g, |&c| c == '#');
+        for _ in 0..10 {
+            b = b.step(&b.von_neumann(), &[1, 2], &[1]);
+            a.step();
+            assert_eq!(b.len(), a.live().len());
+            assert!(a.live().iter().all(|&l| b.is_alive(l)));
+        }
+    }
+}
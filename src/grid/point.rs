@@ -0,0 +1,222 @@
+// Points with any number of coordinates, and points on the layers of a
+// recursive space (a map holding a smaller copy of itself, or a maze
+// with portals going down a level).
+use super::Location;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub, SubAssign};
+
+// Points with neighbours one step away, as used for the searches on
+// maps.
+pub trait Neighbors: Sized {
+    fn neighbors(&self) -> Vec<Self>;
+}
+
+impl Neighbors for Location {
+    fn neighbors(&self) -> Vec<Location> {
+        self.neighbors4().to_vec()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Hash, PartialOrd, Ord, PartialEq, Eq)]
+pub struct PointN<const N: usize>(pub [i64; N]);
+
+pub type Point3 = PointN<3>;
+
+impl<const N: usize> PointN<N> {
+    pub const ORIGIN: PointN<N> = PointN([0; N]);
+
+    // The sum of the absolute values of the coordinates.
+    pub fn norm1(&self) -> i64 {
+        self.0.iter().map(|c| c.abs()).sum()
+    }
+
+    pub fn manhattan(&self, other: &PointN<N>) -> i64 {
+        (*self - *other).norm1()
+    }
+
+    pub fn chebyshev(&self, other: &PointN<N>) -> i64 {
+        (*self - *other).0.iter().map(|c| c.abs()).max().unwrap_or(0)
+    }
+
+    // The sign of each coordinate: a step of at most one along each axis
+    // in the direction of the point.
+    pub fn signum(&self) -> PointN<N> {
+        let mut p = *self;
+        p.0.iter_mut().for_each(|c| *c = c.signum());
+        p
+    }
+}
+
+impl Point3 {
+    pub fn new(x: i64, y: i64, z: i64) -> Point3 {
+        PointN([x, y, z])
+    }
+}
+
+impl<const N: usize> Default for PointN<N> {
+    fn default() -> PointN<N> {
+        PointN::ORIGIN
+    }
+}
+
+impl From<Location> for PointN<2> {
+    fn from(l: Location) -> PointN<2> {
+        PointN([l.x, l.y])
+    }
+}
+
+impl<const N: usize> Index<usize> for PointN<N> {
+    type Output = i64;
+
+    fn index(&self, axis: usize) -> &i64 {
+        &self.0[axis]
+    }
+}
+
+impl<const N: usize> IndexMut<usize> for PointN<N> {
+    fn index_mut(&mut self, axis: usize) -> &mut i64 {
+        &mut self.0[axis]
+    }
+}
+
+impl<const N: usize> Add for PointN<N> {
+    type Output = PointN<N>;
+
+    fn add(mut self, other: PointN<N>) -> PointN<N> {
+        self += other;
+        self
+    }
+}
+
+impl<const N: usize> AddAssign for PointN<N> {
+    fn add_assign(&mut self, other: PointN<N>) {
+        self.0.iter_mut().zip(other.0.iter()).for_each(|(a, b)| *a += b);
+    }
+}
+
+impl<const N: usize> Sub for PointN<N> {
+    type Output = PointN<N>;
+
+    fn sub(mut self, other: PointN<N>) -> PointN<N> {
+        self -= other;
+        self
+    }
+}
+
+impl<const N: usize> SubAssign for PointN<N> {
+    fn sub_assign(&mut self, other: PointN<N>) {
+        self.0.iter_mut().zip(other.0.iter()).for_each(|(a, b)| *a -= b);
+    }
+}
+
+impl<const N: usize> Neg for PointN<N> {
+    type Output = PointN<N>;
+
+    fn neg(self) -> PointN<N> {
+        PointN::ORIGIN - self
+    }
+}
+
+impl<const N: usize> Mul<i64> for PointN<N> {
+    type Output = PointN<N>;
+
+    fn mul(mut self, k: i64) -> PointN<N> {
+        self.0.iter_mut().for_each(|c| *c *= k);
+        self
+    }
+}
+
+// one step back and forth along each axis
+impl<const N: usize> Neighbors for PointN<N> {
+    fn neighbors(&self) -> Vec<PointN<N>> {
+        let mut v = Vec::with_capacity(2 * N);
+        for axis in 0..N {
+            for step in &[-1, 1] {
+                let mut p = *self;
+                p[axis] += step;
+                v.push(p);
+            }
+        }
+        v
+    }
+}
+
+// A point on one of the layers of a recursive space. Layers are numbered
+// going down (inwards) from 0.
+#[derive(Clone, Copy, Debug, Default, Hash, PartialOrd, Ord, PartialEq, Eq)]
+pub struct Layered<L> {
+    pub layer: i64,
+    pub point: L,
+}
+
+impl<L> Layered<L> {
+    pub fn new(layer: i64, point: L) -> Layered<L> {
+        Layered { layer, point }
+    }
+
+    // A point on the same layer.
+    pub fn at(&self, point: L) -> Layered<L> {
+        Layered::new(self.layer, point)
+    }
+
+    // A point on the layer below.
+    pub fn deeper(&self, point: L) -> Layered<L> {
+        Layered::new(self.layer + 1, point)
+    }
+
+    // A point on the layer above.
+    pub fn shallower(&self, point: L) -> Layered<L> {
+        Layered::new(self.layer - 1, point)
+    }
+}
+
+// moves within the layer
+impl<L: Add<Output = L>> Add<L> for Layered<L> {
+    type Output = Layered<L>;
+
+    fn add(self, step: L) -> Layered<L> {
+        Layered::new(self.layer, self.point + step)
+    }
+}
+
+// the neighbours on the same layer
+impl<L: Neighbors> Neighbors for Layered<L> {
+    fn neighbors(&self) -> Vec<Layered<L>> {
+        self.point.neighbors().into_iter().map(|p| Layered::new(self.layer, p)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn arithmetic() {
+        let p = Point3::new(1, -2, 3);
+        let q = Point3::new(-4, 5, 3);
+        assert_eq!(p + q, Point3::new(-3, 3, 6));
+        assert_eq!(p - q, Point3::new(5, -7, 0));
+        assert_eq!(-p * 2, Point3::new(-2, 4, -6));
+        assert_eq!((q - p).signum(), Point3::new(-1, 1, 0));
+        assert_eq!(p.norm1(), 6);
+        assert_eq!(p.manhattan(&q), 12);
+        assert_eq!(p.chebyshev(&q), 7);
+        assert_eq!(PointN::from(Location { x: 2, y: 3 }) * 2, PointN([4, 6]));
+        assert_eq!(Layered::new(1, PointN([1, 1])) + PointN([-1, 2]), Layered::new(1, PointN([0, 3])));
+    }
+
+    #[test]
+    fn neighbors() {
+        let p = PointN([0, 0, 0, 0]);
+        let n = p.neighbors();
+        assert_eq!(n.len(), 8);
+        assert!(n.iter().all(|q| q.manhattan(&p) == 1));
+
+        let l = Layered::new(-1, Location { x: 0, y: 0 });
+        let n: HashSet<_> = l.neighbors().into_iter().collect();
+        assert_eq!(n.len(), 4);
+        assert!(n.contains(&l.at(Location { x: 0, y: -1 })));
+        assert!(!n.contains(&l.deeper(Location { x: 0, y: -1 })));
+        assert_eq!(l.deeper(l.point).shallower(l.point), l);
+    }
+}
@@ -0,0 +1,131 @@
+// Lines of sight on maps. A ray goes from a location in steps of integer
+// length, so it visits exactly the cells on the segment (the lattice
+// points), and the directions are compared exactly, without angles.
+use super::{Grid, Location};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+fn gcd(mut m: i64, mut n: i64) -> i64 {
+    while m != 0 {
+        let old_m = m;
+        m = n % m;
+        n = old_m;
+    }
+    n.abs()
+}
+
+impl Location {
+    // The shortest integer step pointing to `to`, or (0, 0) for the
+    // location itself.
+    pub fn step_towards(&self, to: &Location) -> (i64, i64) {
+        let (dx, dy) = (to.x - self.x, to.y - self.y);
+        match gcd(dx, dy) {
+            0 => (0, 0),
+            g => (dx / g, dy / g),
+        }
+    }
+
+    // The cells strictly between this location and `to`, on the segment
+    // joining them.
+    pub fn line_to(&self, to: &Location) -> impl Iterator<Item = Location> {
+        let (sx, sy) = self.step_towards(to);
+        let steps = gcd(to.x - self.x, to.y - self.y);
+        let from = *self;
+        (1..steps).map(move |i| Location { x: from.x + sx * i, y: from.y + sy * i })
+    }
+
+    // Compares the directions of `a` and `b` from this location, going
+    // clockwise from up.
+    pub fn clockwise_cmp(&self, a: &Location, b: &Location) -> Ordering {
+        let (ax, ay) = (a.x - self.x, a.y - self.y);
+        let (bx, by) = (b.x - self.x, b.y - self.y);
+        // the right half (with straight up) first, then the left half
+        let half = |x: i64, y: i64| x < 0 || (x == 0 && y > 0);
+        half(ax, ay).cmp(&half(bx, by)).then_with(|| 0.cmp(&(ax * by - ay * bx)))
+    }
+}
+
+// Sorts the targets clockwise around `origin` from up, and the ones in
+// the same direction from the nearest. The targets can be locations or
+// references to them.
+pub fn angular_sort<L: Borrow<Location>>(origin: &Location, targets: &mut [L]) {
+    targets.sort_by(|a, b| {
+        let (a, b) = (a.borrow(), b.borrow());
+        origin.clockwise_cmp(a, b).then_with(|| origin.manhattan(a).cmp(&origin.manhattan(b)))
+    });
+}
+
+impl<T> Grid<T> {
+    // The cells from `from` (excluded) with steps of `(dx, dy)`, until
+    // the ray leaves the bounds of the map.
+    pub fn ray(&self, from: Location, (dx, dy): (i64, i64)) -> impl Iterator<Item = Location> + '_ {
+        assert!((dx, dy) != (0, 0), "a ray needs a direction");
+        let inside = move |l: &Location| {
+            (self.x_min()..=self.x_max()).contains(&l.x) && (self.y_min()..=self.y_max()).contains(&l.y)
+        };
+        let start = Location { x: from.x + dx, y: from.y + dy };
+        std::iter::successors(Some(start), move |l| Some(Location { x: l.x + dx, y: l.y + dy }))
+            .take_while(move |l| !self.is_empty() && inside(l))
+    }
+
+    // The first cell along a ray satisfying `blocks`.
+    pub fn first_blocker(&self, from: Location, step: (i64, i64), blocks: impl Fn(&T) -> bool) -> Option<Location> {
+        self.ray(from, step).find(|l| self.get(l).is_some_and(&blocks))
+    }
+
+    // Whether no cell between the two locations satisfies `blocks`.
+    pub fn is_visible(&self, from: &Location, to: &Location, blocks: impl Fn(&T) -> bool) -> bool {
+        from.line_to(to).all(|l| !self.get(&l).is_some_and(&blocks))
+    }
+
+    // The cells satisfying `opaque` seen from `from`: the nearest one in
+    // each direction.
+    pub fn visible_from(&self, from: &Location, opaque: impl Fn(&T) -> bool) -> HashSet<Location> {
+        let mut nearest: HashMap<(i64, i64), Location> = HashMap::new();
+        for l in self.cells_matching(opaque).filter(|l| l != from) {
+            let e = nearest.entry(from.step_towards(&l)).or_insert(l);
+            if from.manhattan(&l) < from.manhattan(e) {
+                *e = l;
+            }
+        }
+        nearest.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines() {
+        let o = Location { x: 0, y: 0 };
+        let to = Location { x: 6, y: -4 };
+        assert_eq!(o.step_towards(&to), (3, -2));
+        assert_eq!(o.line_to(&to).collect::<Vec<_>>(), vec![Location { x: 3, y: -2 }]);
+        assert_eq!(o.line_to(&Location { x: 1, y: 1 }).count(), 0);
+        assert_eq!(o.line_to(&Location { x: 0, y: 3 }).count(), 2);
+
+        let mut targets: Vec<_> = [(-1, -1), (0, 2), (1, 0), (0, -1), (-1, 0), (1, 1), (0, -3), (1, -1)]
+            .iter()
+            .map(|&(x, y)| Location { x, y })
+            .collect();
+        angular_sort(&o, &mut targets);
+        let order: Vec<_> = targets.iter().map(|l| (l.x, l.y)).collect();
+        assert_eq!(order, vec![(0, -1), (0, -3), (1, -1), (1, 0), (1, 1), (0, 2), (-1, 0), (-1, -1)]);
+    }
+
+    #[test]
+    fn visibility() {
+        let g: Grid = Grid::create_from(".#..#\n.....\n#####\n....#\n...##");
+        let rock = |&c: &char| c == '#';
+        let l = Location { x: 3, y: 4 };
+        assert_eq!(g.visible_from(&l, rock).len(), 8);
+        assert!(!g.is_visible(&l, &Location { x: 1, y: 0 }, rock));
+        assert!(g.is_visible(&l, &Location { x: 4, y: 0 }, rock));
+        assert_eq!(g.first_blocker(l, (0, -1), rock), Some(Location { x: 3, y: 2 }));
+        assert_eq!(g.first_blocker(l, (-1, 0), rock), None);
+        assert_eq!(g.ray(l, (1, -1)).count(), 1);
+    }
+}
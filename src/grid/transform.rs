@@ -0,0 +1,219 @@
+// Geometric transformations of grids. The rotations and flips keep the
+// top left corner of the bounds in place, so that a transformed map
+// covers the same area when it is square. They consume the grid and keep
+// its storage (sparse or dense).
+use super::{Bounds, Cells, Glyph, Grid, Location};
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+
+impl<T> Grid<T> {
+    // Turns the map a quarter turn clockwise.
+    pub fn rotate_cw(self) -> Grid<T> {
+        self.transform(|l| Location { x: -l.y, y: l.x })
+    }
+
+    // Turns the map a quarter turn counterclockwise.
+    pub fn rotate_ccw(self) -> Grid<T> {
+        self.transform(|l| Location { x: l.y, y: -l.x })
+    }
+
+    // Mirrors the map left to right.
+    pub fn flip_horizontal(self) -> Grid<T> {
+        self.transform(|l| Location { x: -l.x, y: l.y })
+    }
+
+    // Mirrors the map top to bottom.
+    pub fn flip_vertical(self) -> Grid<T> {
+        self.transform(|l| Location { x: l.x, y: -l.y })
+    }
+
+    // Swaps the rows and the columns.
+    pub fn transpose(self) -> Grid<T> {
+        self.transform(|l| Location { x: l.y, y: l.x })
+    }
+
+    pub fn translate(self, dx: i64, dy: i64) -> Grid<T> {
+        self.remap(|l| Location { x: l.x + dx, y: l.y + dy })
+    }
+
+    // Moves the top left corner of the bounds to (0, 0).
+    pub fn normalize_origin(self) -> Grid<T> {
+        let (dx, dy) = (-self.x_min(), -self.y_min());
+        self.translate(dx, dy)
+    }
+
+    // A view of the cells in a rectangle, with coordinates relative to
+    // its top left corner.
+    pub fn subgrid(&self, xs: RangeInclusive<i64>, ys: RangeInclusive<i64>) -> SubGrid<'_, T> {
+        SubGrid { grid: self, xs, ys }
+    }
+
+    // Applies a linear transformation, then moves the result back to the
+    // top left corner of the bounds.
+    fn transform(self, f: impl Fn(Location) -> Location) -> Grid<T> {
+        let corner = Location { x: self.x_min(), y: self.y_min() };
+        let g = self.remap(f);
+        let (dx, dy) = (corner.x - g.x_min(), corner.y - g.y_min());
+        g.translate(dx, dy)
+    }
+
+    // Moves every cell, keeping the storage.
+    fn remap(self, f: impl Fn(Location) -> Location) -> Grid<T> {
+        let bounds = self.bounds().map(|b| {
+            let (a, c) = (f(Location { x: b.x_min, y: b.y_min }), f(Location { x: b.x_max, y: b.y_max }));
+            Bounds::of(a).extend(c)
+        });
+        let mut g = match (&self.symbols.0, bounds) {
+            (Cells::Dense { .. }, Some(b)) => Grid::dense(b.x_min..=b.x_max, b.y_min..=b.y_max),
+            _ => Grid::new(),
+        };
+        for (l, c) in self.into_cells() {
+            g.insert(f(l), c);
+        }
+        g
+    }
+
+    fn into_cells(self) -> Vec<(Location, T)> {
+        match self.symbols.0 {
+            Cells::Sparse(symbols) => symbols.into_iter().collect(),
+            Cells::Dense { cells, .. } => cells.into_iter().flatten().collect(),
+        }
+    }
+}
+
+impl<T: Clone> Grid<T> {
+    // A copy of the cells in a rectangle, at the same locations.
+    pub fn crop(&self, xs: RangeInclusive<i64>, ys: RangeInclusive<i64>) -> Grid<T> {
+        // only the part of the rectangle within the bounds is allocated
+        let mut g = if self.is_dense() {
+            let x_range = *xs.start().max(&self.x_min())..=*xs.end().min(&self.x_max());
+            let y_range = *ys.start().max(&self.y_min())..=*ys.end().min(&self.y_max());
+            Grid::dense(x_range, y_range)
+        } else {
+            Grid::new()
+        };
+        for (l, c) in self.iter().filter(|(l, _)| xs.contains(&l.x) && ys.contains(&l.y)) {
+            g.insert(l, c.clone());
+        }
+        g
+    }
+}
+
+pub struct SubGrid<'a, T> {
+    grid: &'a Grid<T>,
+    xs: RangeInclusive<i64>,
+    ys: RangeInclusive<i64>,
+}
+
+impl<'a, T> SubGrid<'a, T> {
+    fn absolute(&self, l: &Location) -> Option<Location> {
+        let a = Location { x: l.x + self.xs.start(), y: l.y + self.ys.start() };
+        if self.xs.contains(&a.x) && self.ys.contains(&a.y) {
+            Some(a)
+        } else {
+            None
+        }
+    }
+
+    pub fn width(&self) -> i64 {
+        (self.xs.end() - self.xs.start() + 1).max(0)
+    }
+
+    pub fn height(&self) -> i64 {
+        (self.ys.end() - self.ys.start() + 1).max(0)
+    }
+
+    pub fn get(&self, l: &Location) -> Option<&'a T> {
+        self.absolute(l).and_then(|a| self.grid.get(&a))
+    }
+
+    // Iterates over the cells set in the view, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Location, &'a T)> + '_ {
+        let (x0, y0) = (*self.xs.start(), *self.ys.start());
+        self.grid
+            .iter()
+            .filter(move |(l, _)| self.xs.contains(&l.x) && self.ys.contains(&l.y))
+            .map(move |(l, c)| (Location { x: l.x - x0, y: l.y - y0 }, c))
+    }
+}
+
+impl<'a, T: Clone> SubGrid<'a, T> {
+    // A copy of the view, with its top left corner at (0, 0).
+    pub fn to_grid(&self) -> Grid<T> {
+        self.grid.crop(self.xs.clone(), self.ys.clone()).translate(-self.xs.start(), -self.ys.start())
+    }
+}
+
+// The whole rectangle of the view, unset cells included.
+impl<'a, T: Glyph> Display for SubGrid<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                write!(f, "{}", self.get(&Location { x, y }).map_or(' ', Glyph::glyph))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = "ab.\n..c\n";
+
+    #[test]
+    fn rotations() {
+        for dense in &[true, false] {
+            let mut g = if *dense { Grid::dense(0..=2, 0..=1) } else { Grid::new() };
+            for (l, c) in Grid::<char>::create_from(MAP).iter() {
+                g.insert(l, *c);
+            }
+            let g = g.translate(5, -2);
+            assert_eq!(g.is_dense(), *dense);
+            assert_eq!(g.get(&Location { x: 5, y: -2 }), Some(&'a'));
+
+            let r = g.rotate_cw();
+            assert_eq!(r.to_string(), ".a\n.b\nc.\n");
+            assert_eq!((r.x_min(), r.y_min()), (5, -2));
+            assert_eq!(r.is_dense(), *dense);
+            let r = r.rotate_ccw().rotate_ccw();
+            assert_eq!(r.to_string(), ".c\nb.\na.\n");
+            let r = r.rotate_cw().normalize_origin();
+            assert_eq!(r.to_string(), MAP);
+            assert_eq!((r.x_min(), r.y_min()), (0, 0));
+        }
+    }
+
+    #[test]
+    fn flips() {
+        let g = Grid::<char>::create_from(MAP);
+        assert_eq!(g.clone().flip_horizontal().to_string(), ".ba\nc..\n");
+        assert_eq!(g.clone().flip_vertical().to_string(), "..c\nab.\n");
+        assert_eq!(g.clone().transpose().to_string(), "a.\nb.\n.c\n");
+        assert_eq!(g.transpose().transpose().to_string(), MAP);
+    }
+
+    #[test]
+    fn views() {
+        let g = Grid::<char>::create_from("#####\n#a.b#\n#.c.#\n#####\n");
+        let v = g.subgrid(1..=3, 1..=2);
+        assert_eq!(v.to_string(), "a.b\n.c.\n");
+        assert_eq!(v.get(&Location { x: 1, y: 1 }), Some(&'c'));
+        assert_eq!(v.get(&Location { x: 3, y: 0 }), None);
+        assert_eq!(v.iter().count(), 6);
+        assert_eq!(v.to_grid().find('c'), Some(&Location { x: 1, y: 1 }));
+
+        let c = g.crop(2..=10, 0..=1);
+        assert_eq!(c.len(), 6);
+        assert_eq!(c.find('b'), Some(&Location { x: 3, y: 1 }));
+        assert_eq!(c.to_string(), "###\n.b#\n");
+        let c = g.crop(-1_000_000_000..=1_000_000_000, 1..=1_000_000_000);
+        match c.symbols.0 {
+            Cells::Dense { width, height, .. } => assert_eq!((width, height), (5, 3)),
+            Cells::Sparse(_) => panic!("a dense grid cropped to a sparse one"),
+        }
+        assert_eq!(c.len(), 15);
+    }
+}
@@ -0,0 +1,102 @@
+// A map seen as a graph: the nodes are the passable cells, linked to
+// their passable neighbours up, down, left and right, and through any
+// extra edges (portals).
+use super::{Grid, Location};
+use crate::graph::{Graph, UnweightedGraph};
+use std::collections::HashMap;
+
+type Predicate<'a, T> = Box<dyn Fn(&T) -> bool + 'a>;
+type Stop<'a, T> = Box<dyn Fn(Location, &T) -> bool + 'a>;
+
+pub struct WalkableGrid<'a, T> {
+    grid: &'a Grid<T>,
+    passable: Predicate<'a, T>,
+    stop: Option<Stop<'a, T>>,
+    portals: HashMap<Location, Vec<Location>>,
+}
+
+impl<'a, T> WalkableGrid<'a, T> {
+    pub fn new(grid: &'a Grid<T>, passable: impl Fn(&T) -> bool + 'a) -> WalkableGrid<'a, T> {
+        WalkableGrid {
+            grid,
+            passable: Box::new(passable),
+            stop: None,
+            portals: HashMap::new(),
+        }
+    }
+
+    // Makes the cells satisfying `stop` dead ends: they can be reached,
+    // but not walked through.
+    pub fn with_stop(mut self, stop: impl Fn(Location, &T) -> bool + 'a) -> Self {
+        self.stop = Some(Box::new(stop));
+        self
+    }
+
+    // Adds an edge from `from` to `to`, one way.
+    pub fn with_portal(mut self, from: Location, to: Location) -> Self {
+        self.portals.entry(from).or_default().push(to);
+        self
+    }
+
+    pub fn with_portals(self, portals: impl IntoIterator<Item = (Location, Location)>) -> Self {
+        portals.into_iter().fold(self, |g, (from, to)| g.with_portal(from, to))
+    }
+
+    pub fn is_passable(&self, l: &Location) -> bool {
+        self.grid.get(l).is_some_and(|c| (self.passable)(c))
+    }
+
+    fn walk(&self, node: &Location) -> Vec<Location> {
+        if let (Some(stop), Some(c)) = (&self.stop, self.grid.get(node)) {
+            if stop(*node, c) {
+                return Vec::new();
+            }
+        }
+        let mut v: Vec<_> = node.neighbors4().iter().filter(|n| self.is_passable(n)).copied().collect();
+        if let Some(to) = self.portals.get(node) {
+            v.extend(to);
+        }
+        v
+    }
+}
+
+impl<'a, T> UnweightedGraph<Location> for WalkableGrid<'a, T> {
+    fn edges(&self, node: &Location) -> Vec<Location> {
+        self.walk(node)
+    }
+}
+
+// Every step has length 1.
+impl<'a, T> Graph<Location> for WalkableGrid<'a, T> {
+    fn edges(&self, node: &Location) -> Vec<(Location, usize)> {
+        self.walk(node).into_iter().map(|l| (l, 1)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths() {
+        let g: Grid = Grid::create_from("#######\n#a..#b#\n#.#.#.#\n#..x..#\n#######");
+        let a = *g.find('a').unwrap();
+        let b = *g.find('b').unwrap();
+        let walk = WalkableGrid::new(&g, |&c| c != '#');
+        let bfs = UnweightedGraph::shortest_paths(&walk, a, &[]);
+        assert_eq!(bfs[&b], 8);
+        assert_eq!(Graph::shortest_paths(&walk, a), bfs);
+
+        // stop at the items
+        let x = *g.find('x').unwrap();
+        let walk = WalkableGrid::new(&g, |&c| c != '#').with_stop(|l, &c| l != a && c != '.');
+        let bfs = UnweightedGraph::shortest_paths(&walk, a, &[]);
+        assert_eq!(bfs[&x], 4);
+        assert!(!bfs.contains_key(&b));
+
+        // a shortcut
+        let walk = WalkableGrid::new(&g, |&c| c != '#').with_portal(a, b);
+        assert_eq!(UnweightedGraph::shortest_paths(&walk, a, &[])[&b], 1);
+        assert_eq!(Graph::bfs_paths(&walk, b)[&a], 8);
+    }
+}
@@ -0,0 +1,104 @@
+// The Space Image Format: the digits of a picture, layer after layer,
+// each layer being `width` x `height` pixels. Pixels are 0 (black),
+// 1 (white) or 2 (transparent).
+use crate::grid::{Grid, Location};
+
+pub struct SpaceImage {
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>, // the digits, as ASCII
+}
+
+impl SpaceImage {
+    pub fn new(width: usize, height: usize, data: &[u8]) -> SpaceImage {
+        SpaceImage { width, height, data: data.to_vec() }
+    }
+
+    // The number of 0, 1 and 2 digits in each layer.
+    pub fn count012(&self) -> Vec<(usize, usize, usize)> {
+        self.data
+            .chunks_exact(self.width * self.height)
+            .map(|l| {
+                l.iter().fold((0, 0, 0), |(n0, n1, n2), d| match d {
+                    b'0' => (n0 + 1, n1, n2),
+                    b'1' => (n0, n1 + 1, n2),
+                    b'2' => (n0, n1, n2 + 1),
+                    _ => (n0, n1, n2),
+                })
+            })
+            .collect()
+    }
+
+    // The visible pixels, the first non transparent one of each stack.
+    pub fn compose_layers(&self) -> Vec<u8> {
+        let mut res: Vec<u8> = vec![b'0'; self.width * self.height];
+
+        for (i, pixel) in res.iter_mut().enumerate() {
+            *pixel = *self
+                .data
+                .iter()
+                .skip(i)
+                .step_by(self.width * self.height)
+                .find(|&d| d != &b'2')
+                .unwrap_or(&b'0');
+        }
+        res
+    }
+
+    pub fn decode(&self) -> String {
+        let mut it = self.compose_layers().into_iter();
+        let mut s = String::new();
+        for _ in 0..self.height {
+            for _ in 0..self.width {
+                if it.next().unwrap() == b'1' {
+                    s.push('\u{2588}')
+                } else {
+                    s.push(' ')
+                };
+            }
+            s.push('\n');
+        }
+        s
+    }
+
+    pub fn layers(&self) -> usize {
+        self.data.len() / (self.width * self.height)
+    }
+
+    // A layer as a grid of digits (0, 1, 2), to export it.
+    pub fn layer(&self, n: usize) -> Grid<u8> {
+        let size = self.width * self.height;
+        self.to_grid(&self.data[n * size..(n + 1) * size])
+    }
+
+    // The composed image as a grid of digits (0 or 1).
+    pub fn composed(&self) -> Grid<u8> {
+        self.to_grid(&self.compose_layers())
+    }
+
+    fn to_grid(&self, pixels: &[u8]) -> Grid<u8> {
+        let mut g = Grid::dense(0..=self.width as i64 - 1, 0..=self.height as i64 - 1);
+        for (i, p) in pixels.iter().enumerate() {
+            let l = Location { x: (i % self.width) as i64, y: (i / self.width) as i64 };
+            g.insert(l, p - b'0');
+        }
+        g
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers() {
+        let img = SpaceImage::new(2, 2, b"0222112222120000");
+        assert_eq!(img.compose_layers(), b"0110".to_vec());
+        assert_eq!(img.decode(), " \u{2588}\n\u{2588} \n");
+        assert_eq!(img.layers(), 4);
+        assert_eq!(img.layer(1).get(&Location { x: 0, y: 0 }), Some(&1));
+        assert_eq!(img.layer(1).get(&Location { x: 1, y: 1 }), Some(&2));
+        assert_eq!(img.composed().get(&Location { x: 1, y: 0 }), Some(&1));
+        assert_eq!(img.count012(), vec![(1, 0, 3), (0, 2, 2), (0, 1, 3), (4, 0, 0)]);
+    }
+}
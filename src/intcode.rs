@@ -1,14 +1,82 @@
+use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::convert::TryInto;
+use std::rc::Rc;
+
+pub mod analysis;
+pub mod compiler;
+pub mod conformance;
+pub mod controller;
+pub mod dump;
+pub mod frame;
+pub mod gdb;
+pub mod mock;
+pub mod observer;
+pub mod optimizer;
+pub mod pool;
+pub mod scheduler;
+pub mod symbolic;
+
+use frame::{Frame, Frames};
+use observer::{Event, Observer};
+
+// A common interface for Intcode machines, so that alternative
+// interpreters can be driven (and checked) by the same code.
+pub trait Machine {
+    fn push_input(&mut self, value: i64);
+    fn run(&mut self);
+    fn take_output(&mut self) -> Vec<i64>;
+    fn read(&self, addr: usize) -> i64;
+    fn is_halted(&self) -> bool;
+
+    // Sends an ASCII command (if not empty) followed by a line feed, runs
+    // the machine and splits its output into text and other values.
+    fn run_ascii_command(&mut self, command: &str) -> (String, Vec<i64>) {
+        if !command.is_empty() {
+            for c in command.bytes() {
+                self.push_input(c as i64);
+            }
+            self.push_input(b'\n' as i64);
+        }
+        self.run();
+        split_ascii(self.take_output())
+    }
+}
+
+// Splits an output into its ASCII text and the other values.
+fn split_ascii(output: impl IntoIterator<Item = i64>) -> (String, Vec<i64>) {
+    let mut s = String::new();
+    let mut rests = vec![];
+    for c in output {
+        match c {
+            n if n > 0 && n < 128 => { s.push(n as u8 as char); },
+            _ => rests.push(c),
+        }
+    }
+    (s, rests)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Running,      // the last instruction was executed
+    WaitingInput, // blocked on an input instruction
+    Halted,
+}
+
+// Size of the memory pages tracked for copy-on-write restores.
+const PAGE_SIZE: usize = 64;
 
 #[derive(Clone)]
 pub struct IntCode {
-    pub memory: Vec<i64>,     // memory
-    pub input: VecDeque<i64>, // input buffer
-    pub output: Vec<i64>,     // output buffer
-    pub display: bool,        // switch display on/off
-    ptr: usize,               // instruction pointer
-    base: i64,                // relative base
+    pub memory: Vec<i64>,                            // memory
+    pub input: VecDeque<i64>,                        // input buffer
+    pub output: Vec<i64>,                            // output buffer
+    pub observer: Option<Rc<RefCell<dyn Observer>>>, // receives the events, if any
+    ptr: usize,                                      // instruction pointer
+    base: i64,                                       // relative base
+    image: Option<Rc<[i64]>>,                        // pristine program, shared
+    dirty: Vec<bool>,                                // pages written since the last restore
+    halt_reported: bool,                             // the observer was told of the halt
 }
 
 impl IntCode {
@@ -17,49 +85,196 @@ impl IntCode {
             memory: program.to_vec(),
             input: VecDeque::new(),
             output: Vec::new(),
-            display: true,
+            observer: None,
             ptr: 0,
             base: 0,
+            image: None,
+            dirty: Vec::new(),
+            halt_reported: false,
+        }
+    }
+
+    // Creates a machine that shares the pristine program image, so that
+    // `restore` only needs to copy back the pages written by the program.
+    pub fn from_image(image: &Rc<[i64]>) -> IntCode {
+        let mut c = IntCode::new(image);
+        c.image = Some(image.clone());
+        c
+    }
+
+    // Brings a machine created with `from_image` back to its initial
+    // state. Only writes done by the program or with `poke` are undone,
+    // not the ones done directly on `memory`.
+    pub fn restore(&mut self) {
+        let image = self.image.as_ref().expect("no program image to restore");
+        if self.memory.len() < image.len() {
+            self.memory.clear();
+            self.memory.extend_from_slice(image);
+        } else {
+            self.memory.truncate(image.len());
+            for (page, _) in self.dirty.iter().enumerate().filter(|(_, d)| **d) {
+                let start = page * PAGE_SIZE;
+                if start < image.len() {
+                    let end = image.len().min(start + PAGE_SIZE);
+                    self.memory[start..end].copy_from_slice(&image[start..end]);
+                }
+            }
         }
+        self.dirty.clear();
+        self.input.clear();
+        self.output.clear();
+        self.ptr = 0;
+        self.base = 0;
+        self.halt_reported = false;
+    }
+
+    // Writes a value in memory, keeping track of the modified page.
+    pub fn poke(&mut self, addr: usize, value: i64) {
+        if addr >= self.memory.len() {
+            self.memory.resize(addr + 1, 0);
+        }
+        self.write(addr, value);
+    }
+
+    fn write(&mut self, addr: usize, value: i64) {
+        self.memory[addr] = value;
+        let page = addr / PAGE_SIZE;
+        if page >= self.dirty.len() {
+            self.dirty.resize(page + 1, false);
+        }
+        self.dirty[page] = true;
     }
 
     pub fn reset(&mut self, program: &[i64]) {
         self.memory.clear();
         self.memory.extend_from_slice(program);
+        self.dirty.clear();
         self.input.clear();
         self.output.clear();
         self.ptr = 0;
         self.base = 0;
+        self.halt_reported = false;
+    }
+
+    // Attaches an observer to the machine, returning a handle to it.
+    // Clones of the machine share the same observer.
+    pub fn observe<O: Observer + 'static>(&mut self, observer: O) -> Rc<RefCell<O>> {
+        let o = Rc::new(RefCell::new(observer));
+        self.observer = Some(o.clone());
+        o
+    }
+
+    fn notify(&self, event: Event) {
+        if let Some(o) = &self.observer {
+            o.borrow_mut().notify(&event);
+        }
+    }
+
+    // Executes at most `n` instructions, then tells the observer about
+    // the output they produced and, once, that the machine halted.
+    fn execute_reported(&mut self, n: usize) -> Status {
+        let start = self.output.len();
+        let status = self.execute(n);
+        if self.observer.is_some() {
+            let (s, rests) = split_ascii(self.output[start..].iter().copied());
+            if !s.is_empty() {
+                self.notify(Event::TextReceived(s));
+            }
+            for v in rests {
+                self.notify(Event::ValueReceived(v));
+            }
+            if !self.halt_reported && self.is_halted() {
+                self.halt_reported = true;
+                self.notify(Event::Halted);
+            }
+        }
+        status
     }
 
     pub fn is_halted(&self) -> bool {
         self.memory[self.ptr] == 99
     }
 
+    pub fn ptr(&self) -> usize {
+        self.ptr
+    }
+
+    pub fn set_ptr(&mut self, ptr: usize) {
+        self.ptr = ptr;
+        self.halt_reported = false;
+    }
+
+    pub fn base(&self) -> i64 {
+        self.base
+    }
+
+    pub fn set_base(&mut self, base: i64) {
+        self.base = base;
+    }
+
+    // Runs until the program halts or needs more input.
     pub fn run(&mut self) {
-        loop {
+        self.execute_reported(usize::MAX);
+    }
+
+    // Executes a single instruction, if possible.
+    pub fn step(&mut self) -> Status {
+        self.execute_reported(1)
+    }
+
+    // Executes at most `n` instructions, stopping early if the program
+    // halts or needs more input.
+    pub fn run_for(&mut self, n: usize) -> Status {
+        self.execute_reported(n)
+    }
+
+    // Executes at most `n` instructions.
+    #[inline(always)]
+    fn execute(&mut self, mut n: usize) -> Status {
+        while n > 0 {
             let (op, op_len, (a1, a2, a3)) = self.decode(self.memory[self.ptr]);
             match op {
-                 1 => { self.memory[a3] = self.memory[a1] + self.memory[a2]; self.ptr += op_len; },
-                 2 => { self.memory[a3] = self.memory[a1] * self.memory[a2]; self.ptr += op_len; },
-                 3 => { if self.input.is_empty() { break; }; self.memory[a1] = self.input.pop_front().unwrap(); self.ptr += op_len; },
+                 1 => { self.write(a3, self.memory[a1] + self.memory[a2]); self.ptr += op_len; },
+                 2 => { self.write(a3, self.memory[a1] * self.memory[a2]); self.ptr += op_len; },
+                 3 => { if self.input.is_empty() { return Status::WaitingInput; }; let v = self.input.pop_front().unwrap(); self.write(a1, v); self.ptr += op_len; },
                  4 => { self.output.push(self.memory[a1]); self.ptr += op_len; },
                  5 => { if self.memory[a1] != 0 { self.ptr = self.memory[a2].try_into().unwrap(); } else { self.ptr += op_len; } },
                  6 => { if self.memory[a1] == 0 { self.ptr = self.memory[a2].try_into().unwrap(); } else { self.ptr += op_len; } },
-                 7 => { if self.memory[a1] < self.memory[a2] { self.memory[a3] = 1; } else { self.memory[a3] = 0; }; self.ptr += op_len; },
-                 8 => { if self.memory[a1] == self.memory[a2] { self.memory[a3] = 1; } else { self.memory[a3] = 0; }; self.ptr += op_len; },
+                 7 => { self.write(a3, (self.memory[a1] < self.memory[a2]) as i64); self.ptr += op_len; },
+                 8 => { self.write(a3, (self.memory[a1] == self.memory[a2]) as i64); self.ptr += op_len; },
                  9 => { self.base += self.memory[a1]; self.ptr += op_len; },
-                99 => break,
+                99 => { return Status::Halted; },
                  _ => panic!("invalid opcode {}", op),
             }
+            n -= 1;
         }
+        Status::Running
+    }
+
+    // Takes the first `n` values of the output, if they are all available.
+    pub fn take_exact(&mut self, n: usize) -> Option<Vec<i64>> {
+        if self.output.len() < n {
+            return None;
+        }
+        Some(self.output.drain(..n).collect())
+    }
+
+    // Takes the first frame of the output, if it is complete.
+    pub fn next_frame<F: Frame>(&mut self) -> Option<F> {
+        self.frames().next()
+    }
+
+    // Iterates over the complete frames of the output, leaving any
+    // incomplete frame in the buffer.
+    pub fn frames<F: Frame>(&mut self) -> Frames<'_, F> {
+        Frames::new(&mut self.output)
     }
 
     pub fn run_ascii_command(&mut self, command: &str) -> (String, Vec<i64>) {
         // load command
         if !command.is_empty() {
-            if self.display {
-                println!("{}", command);
+            if self.observer.is_some() {
+                self.notify(Event::CommandSent(command.to_string()));
             }
             for c in command.bytes() {
                 self.input.push_back(c as i64);
@@ -68,25 +283,15 @@ impl IntCode {
         }
 
         // execute
-        self.run();
+        self.execute_reported(usize::MAX);
 
         // extract output
-        let mut s = String::new();
-        let mut rests = vec![];
-
-        for c in self.output.drain(..) {
-            match c {
-                n if n > 0 && n < 128 => { s.push(n as u8 as char); },
-                _ => rests.push(c),
-            }
-        }
-        if self.display {
-            print!("{}", s);
-        }
-        (s, rests)
+        split_ascii(self.output.drain(..))
     }
 
     // set the opcode, its length, and the parameter addresses for the current instruction
+    #[inline(always)]
+    #[allow(clippy::needless_range_loop)]
     fn decode(&mut self, mut instr: i64) -> (i64, usize, (usize, usize, usize)) {
         let op = instr % 100;
         instr /= 100;
@@ -116,3 +321,31 @@ impl IntCode {
         (op, op_len, (addr[0], addr[1], addr[2]))
     }
 }
+
+impl Machine for IntCode {
+    fn push_input(&mut self, value: i64) {
+        self.input.push_back(value);
+    }
+
+    fn run(&mut self) {
+        IntCode::run(self);
+    }
+
+    fn take_output(&mut self) -> Vec<i64> {
+        self.output.drain(..).collect()
+    }
+
+    // cells beyond the end of the memory read as zero
+    fn read(&self, addr: usize) -> i64 {
+        self.memory.get(addr).copied().unwrap_or(0)
+    }
+
+    fn is_halted(&self) -> bool {
+        IntCode::is_halted(self)
+    }
+
+    // also reports the command and the output to the observer
+    fn run_ascii_command(&mut self, command: &str) -> (String, Vec<i64>) {
+        IntCode::run_ascii_command(self, command)
+    }
+}
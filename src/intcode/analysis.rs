@@ -0,0 +1,710 @@
+// Static analysis of Intcode programs by abstract interpretation.
+//
+// Values are approximated by small sets of constants, or by intervals
+// when the sets grow too large. The relative base is tracked for each
+// instruction, while the memory is tracked globally (the join of all
+// the values written in each cell), leaving out the writes done by
+// instructions that can't run before the one reading the cell. Jumps to computed targets are
+// followed when the targets are known constants, like the return
+// addresses pushed on the stack by function calls.
+//
+// The analysis reports reads of cells beyond the program image that
+// are never written (the machine silently reads them as zero), writes
+// into code that may run afterwards, jumps, addresses and relative
+// bases that may be negative (the machine panics on them), and the
+// computed jumps and patched instructions that could not be resolved,
+// where the analysis stops following the program.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Display;
+use std::rc::Rc;
+
+const MAX_SET: usize = 8; // largest set of constants before switching to intervals
+const MAX_CHANGES: usize = 8; // changes to a value before widening it
+const MAX_ROUNDS: usize = 100;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Value {
+    Set(BTreeSet<i64>),
+    Range(i64, i64), // i64::MIN and i64::MAX stand for unbounded
+}
+
+impl Value {
+    fn constant(v: i64) -> Value {
+        Value::Set(std::iter::once(v).collect())
+    }
+
+    fn top() -> Value {
+        Value::Range(i64::MIN, i64::MAX)
+    }
+
+    fn bounds(&self) -> (i64, i64) {
+        match self {
+            Value::Set(s) => (*s.iter().next().unwrap(), *s.iter().next_back().unwrap()),
+            Value::Range(lo, hi) => (*lo, *hi),
+        }
+    }
+
+    fn may_be_negative(&self) -> bool {
+        self.bounds().0 < 0
+    }
+
+    fn contains(&self, v: i64) -> bool {
+        match self {
+            Value::Set(s) => s.contains(&v),
+            Value::Range(lo, hi) => *lo <= v && v <= *hi,
+        }
+    }
+
+    fn from_set(s: BTreeSet<i64>) -> Value {
+        if s.len() <= MAX_SET {
+            Value::Set(s)
+        } else {
+            Value::Range(*s.iter().next().unwrap(), *s.iter().next_back().unwrap())
+        }
+    }
+
+    fn join(&self, other: &Value) -> Value {
+        match (self, other) {
+            (Value::Set(a), Value::Set(b)) => Value::from_set(a.union(b).copied().collect()),
+            _ => {
+                let (a, b) = self.bounds();
+                let (c, d) = other.bounds();
+                Value::Range(a.min(c), b.max(d))
+            }
+        }
+    }
+
+    // Joins `other`, pushing to infinity the bounds that moved.
+    fn widen(&self, other: &Value) -> Value {
+        let (a, b) = self.bounds();
+        let (c, d) = other.join(self).bounds();
+        Value::Range(if c < a { i64::MIN } else { a }, if d > b { i64::MAX } else { b })
+    }
+
+    // Applies an operation, pairwise on sets or on the bounds of intervals.
+    fn apply(&self, other: &Value, f: fn(i64, i64) -> Option<i64>, on_bounds: bool) -> Value {
+        if let (Value::Set(a), Value::Set(b)) = (self, other) {
+            if a.len() * b.len() <= MAX_SET * MAX_SET {
+                let mut s = BTreeSet::new();
+                for x in a.iter() {
+                    for y in b.iter() {
+                        match f(*x, *y) {
+                            Some(v) => s.insert(v),
+                            None => return Value::top(),
+                        };
+                    }
+                }
+                return Value::from_set(s);
+            }
+        }
+        if !on_bounds {
+            return Value::top();
+        }
+        let (a, b) = self.bounds();
+        let (c, d) = other.bounds();
+        let corners = [f(a, c), f(a, d), f(b, c), f(b, d)];
+        if corners.iter().any(|v| v.is_none()) {
+            return Value::top();
+        }
+        let lo = corners.iter().map(|v| v.unwrap()).min().unwrap();
+        let hi = corners.iter().map(|v| v.unwrap()).max().unwrap();
+        Value::Range(lo, hi)
+    }
+
+    fn add(&self, other: &Value) -> Value {
+        self.apply(other, |x, y| Some(x.saturating_add(y)), true)
+    }
+
+    fn mul(&self, other: &Value) -> Value {
+        self.apply(other, |x, y| Some(x.saturating_mul(y)), true)
+    }
+
+    fn less_than(&self, other: &Value) -> Value {
+        let (a, b) = self.bounds();
+        let (c, d) = other.bounds();
+        if b < c {
+            Value::constant(1)
+        } else if a >= d {
+            Value::constant(0)
+        } else {
+            Value::from_set([0, 1].iter().copied().collect())
+        }
+    }
+
+    fn equals(&self, other: &Value) -> Value {
+        match (self, other) {
+            (Value::Set(a), Value::Set(b)) if a.len() == 1 && a == b => Value::constant(1),
+            (Value::Set(a), Value::Set(b)) if a.is_disjoint(b) => Value::constant(0),
+            _ => {
+                let (a, b) = self.bounds();
+                let (c, d) = other.bounds();
+                if b < c || d < a {
+                    Value::constant(0)
+                } else {
+                    Value::from_set([0, 1].iter().copied().collect())
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Warning {
+    InvalidInstruction { at: usize },
+    UninitializedRead { at: usize, addr: usize },
+    WriteToCode { at: usize, addr: usize },
+    NegativeAddress { at: usize },
+    NegativeBase { at: usize },
+    NegativeJump { at: usize },
+    UnresolvedJump { at: usize },
+    UnresolvedInstruction { at: usize },
+}
+
+impl Warning {
+    // whether the analysis could not follow the program from there
+    fn is_unresolved(&self) -> bool {
+        matches!(self, Warning::UnresolvedJump { .. } | Warning::UnresolvedInstruction { .. })
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Warning::InvalidInstruction { at } => write!(f, "{}: invalid instruction", at),
+            Warning::UninitializedRead { at, addr } => {
+                write!(f, "{}: reads cell {}, beyond the program and never written", at, addr)
+            }
+            Warning::WriteToCode { at, addr } => write!(f, "{}: writes into the code at {}", at, addr),
+            Warning::NegativeAddress { at } => write!(f, "{}: address may be negative", at),
+            Warning::NegativeBase { at } => write!(f, "{}: relative base may become negative", at),
+            Warning::NegativeJump { at } => write!(f, "{}: jump target may be negative", at),
+            Warning::UnresolvedJump { at } => write!(f, "{}: jump target could not be determined", at),
+            Warning::UnresolvedInstruction { at } => {
+                write!(f, "{}: patched with an instruction that could not be determined", at)
+            }
+        }
+    }
+}
+
+pub struct Report {
+    pub instructions: usize, // instructions found reachable
+    pub warnings: Vec<Warning>,
+}
+
+impl Report {
+    // Whether the whole program was followed: if not, more instructions
+    // may be reachable, with more warnings.
+    pub fn is_complete(&self) -> bool {
+        !self.warnings.iter().any(|w| w.is_unresolved())
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let incomplete = if self.is_complete() { "" } else { " (incomplete)" };
+        writeln!(f, "{} reachable instructions{}, {} warnings", self.instructions, incomplete, self.warnings.len())?;
+        for w in self.warnings.iter() {
+            writeln!(f, "  {}", w)?;
+        }
+        Ok(())
+    }
+}
+
+enum Addr {
+    Immediate(Value),
+    Cells(Vec<usize>),
+    Wild(usize, usize), // some cell in a range
+}
+
+struct Analyzer<'a> {
+    program: &'a [i64],
+    memory: HashMap<usize, (Value, usize)>,           // values written and number of changes
+    wild: HashMap<usize, (usize, usize, Value)>,      // writes to unknown cells, by instruction
+    bases: HashMap<usize, (Value, usize)>,            // relative bases reaching each instruction
+    preds: HashMap<usize, BTreeSet<Option<usize>>>,   // instructions flowing to each instruction
+    ancestors: HashMap<usize, Rc<BTreeSet<usize>>>,   // cache of the instructions running before each one
+    current: Option<usize>,                           // the instruction being interpreted
+    todo: BTreeSet<usize>,
+    code: BTreeSet<usize>,
+    spans: BTreeMap<usize, usize>,                    // the longest instruction run at each address
+    reads: BTreeSet<(usize, usize)>,
+    writes: BTreeSet<(usize, usize)>,
+    writers: HashMap<usize, BTreeSet<usize>>,         // instructions writing each cell
+    warnings: BTreeSet<Warning>,
+    changed: bool,
+}
+
+impl<'a> Analyzer<'a> {
+    // The values a cell may hold. Cells beyond the image are assumed
+    // to be written before they are read (reads of cells never written
+    // are reported separately).
+    fn read(&mut self, at: usize, addr: usize) -> Value {
+        if addr >= self.program.len() {
+            self.reads.insert((at, addr));
+        }
+        if !self.written_before(at, addr) {
+            return Value::constant(self.program.get(addr).copied().unwrap_or(0));
+        }
+        let mut v = self.cell(addr);
+        for (lo, hi, w) in self.wild.values() {
+            if *lo <= addr && addr <= *hi {
+                v = Some(v.map_or(w.clone(), |v| v.join(w)));
+            }
+        }
+        v.unwrap_or_else(|| Value::constant(0))
+    }
+
+    // The values of a cell, ignoring writes to unknown cells.
+    fn cell(&self, addr: usize) -> Option<Value> {
+        let written = self.memory.get(&addr).map(|(w, _)| w);
+        match (self.program.get(addr), written) {
+            (Some(v), Some(w)) => Some(w.join(&Value::constant(*v))),
+            (Some(v), None) => Some(Value::constant(*v)),
+            (None, w) => w.cloned(),
+        }
+    }
+
+    // Whether a cell may be written before the instruction at `at` runs.
+    fn written_before(&mut self, at: usize, addr: usize) -> bool {
+        let wild = |w: &(usize, usize, Value)| w.0 <= addr && addr <= w.1;
+        if !self.writers.contains_key(&addr) && !self.wild.values().any(wild) {
+            return false;
+        }
+        let before = self.ancestors(at);
+        self.writers.get(&addr).is_some_and(|w| w.iter().any(|w| before.contains(w)))
+            || self.wild.iter().any(|(w, range)| wild(range) && before.contains(w))
+    }
+
+    // The values of a cell of the instruction at `at`. A cell written
+    // only by instructions that can't run before this one holds its
+    // original value. A cell patched only by the previous instruction,
+    // when that is the only way here, holds the patched values only: the
+    // original one is overwritten (the usual idiom for indexed accesses).
+    fn code_cell(&mut self, at: usize, addr: usize) -> Option<Value> {
+        if !self.written_before(at, addr) {
+            return self.program.get(addr).map(|v| Value::constant(*v));
+        }
+
+        let patcher = match self.preds.get(&at).map(|p| p.iter().collect::<Vec<_>>()).as_deref() {
+            Some([Some(w)]) => *w,
+            _ => return self.cell(addr),
+        };
+        let mut writes = self.writes.range((patcher, 0)..=(patcher, usize::MAX));
+        let patched = writes.next() == Some(&(patcher, addr))
+            && writes.next().is_none()
+            && self.writers.get(&addr).is_some_and(|w| w.iter().all(|w| *w == patcher))
+            && !self.wild.values().any(|(lo, hi, _)| *lo <= addr && addr <= *hi);
+        match self.memory.get(&addr) {
+            Some((v, _)) if patched => Some(v.clone()),
+            _ => self.cell(addr),
+        }
+    }
+
+    fn write(&mut self, at: usize, addr: &Addr, v: Value) {
+        match addr {
+            Addr::Cells(cells) => {
+                for c in cells.iter() {
+                    self.writes.insert((at, *c));
+                    self.writers.entry(*c).or_default().insert(at);
+                    let (old, n) = self.memory.get(c).cloned().unwrap_or((v.clone(), 0));
+                    let new = if n >= MAX_CHANGES { old.widen(&v) } else { old.join(&v) };
+                    if n == 0 || new != old {
+                        self.memory.insert(*c, (new, n + 1));
+                        self.changed = true;
+                    }
+                }
+            }
+            Addr::Wild(lo, hi) => {
+                let new = match self.wild.get(&at) {
+                    Some((l, h, old)) => ((*l).min(*lo), (*h).max(*hi), old.widen(&v)),
+                    None => (*lo, *hi, v),
+                };
+                if self.wild.get(&at) != Some(&new) {
+                    self.wild.insert(at, new);
+                    self.changed = true;
+                }
+            }
+            Addr::Immediate(_) => {
+                self.warnings.insert(Warning::InvalidInstruction { at });
+            }
+        }
+    }
+
+    // The instructions that may run before the one at `at`.
+    fn ancestors(&mut self, at: usize) -> Rc<BTreeSet<usize>> {
+        if let Some(a) = self.ancestors.get(&at) {
+            return a.clone();
+        }
+        let mut seen = BTreeSet::new();
+        let mut todo = vec![at];
+        while let Some(i) = todo.pop() {
+            for from in self.preds.get(&i).into_iter().flatten().flatten() {
+                if seen.insert(*from) {
+                    todo.push(*from);
+                }
+            }
+        }
+        let a = Rc::new(seen);
+        self.ancestors.insert(at, a.clone());
+        a
+    }
+
+    // The cells of the instructions that may run after the one at `at`.
+    fn code_after(&self, at: usize) -> BTreeSet<usize> {
+        let mut next: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (to, froms) in self.preds.iter() {
+            for from in froms.iter().flatten() {
+                next.entry(*from).or_default().push(*to);
+            }
+        }
+        let mut seen = BTreeSet::new();
+        let mut todo = next.get(&at).cloned().unwrap_or_default();
+        while let Some(i) = todo.pop() {
+            if seen.insert(i) {
+                todo.extend(next.get(&i).into_iter().flatten());
+            }
+        }
+        seen.iter().flat_map(|i| *i..*i + self.spans.get(i).copied().unwrap_or(1)).collect()
+    }
+
+    // Reports the writes that may land on `addr`.
+    fn report_writes(&mut self, addr: usize) {
+        let writers = self.writers.get(&addr).into_iter().flatten().copied();
+        let wild = self.wild.iter().filter(|(_, (lo, hi, _))| *lo <= addr && addr <= *hi).map(|(at, _)| *at);
+        let warnings: Vec<_> = writers.chain(wild).map(|at| Warning::WriteToCode { at, addr }).collect();
+        self.warnings.extend(warnings);
+    }
+
+    fn value(&mut self, at: usize, addr: &Addr) -> Value {
+        match addr {
+            Addr::Immediate(v) => v.clone(),
+            Addr::Cells(cells) if cells.is_empty() => Value::top(),
+            Addr::Cells(cells) => {
+                let values: Vec<_> = cells.iter().map(|c| self.read(at, *c)).collect();
+                values.iter().skip(1).fold(values[0].clone(), |v, w| v.join(w))
+            }
+            Addr::Wild(lo, hi) if hi - lo < 1024 => {
+                let mut v = self.read(at, *lo);
+                for c in *lo + 1..=*hi {
+                    let w = self.read(at, c);
+                    v = v.join(&w);
+                }
+                v
+            }
+            Addr::Wild(..) => Value::top(),
+        }
+    }
+
+    fn address(&mut self, at: usize, mode: i64, param: &Value, base: &Value) -> Option<Addr> {
+        let cells = match mode {
+            0 => param.clone(),
+            1 => return Some(Addr::Immediate(param.clone())),
+            2 => base.add(param),
+            _ => return None,
+        };
+        if cells.may_be_negative() {
+            self.warnings.insert(Warning::NegativeAddress { at });
+        }
+        match cells {
+            Value::Set(s) => Some(Addr::Cells(s.into_iter().filter(|c| *c >= 0).map(|c| c as usize).collect())),
+            Value::Range(lo, hi) => Some(Addr::Wild(lo.max(0) as usize, hi.max(0) as usize)),
+        }
+    }
+
+    // Records that `base` can reach instruction `at`.
+    fn flow(&mut self, at: usize, base: &Value) {
+        let (new, n) = match self.bases.get(&at) {
+            None => (base.clone(), 1),
+            Some((old, n)) if *n >= MAX_CHANGES => (old.widen(base), n + 1),
+            Some((old, n)) => (old.join(base), n + 1),
+        };
+        if self.preds.entry(at).or_default().insert(self.current) {
+            self.ancestors.clear();
+            self.changed = true;
+        }
+        if self.bases.get(&at).map(|(old, _)| old) != Some(&new) {
+            self.bases.insert(at, (new, n));
+            self.todo.insert(at);
+        }
+    }
+
+    fn jump(&mut self, at: usize, target: &Value, base: &Value) {
+        if target.may_be_negative() {
+            self.warnings.insert(Warning::NegativeJump { at });
+        }
+        match target {
+            Value::Set(s) => {
+                for t in s.iter().filter(|t| **t >= 0) {
+                    self.flow(*t as usize, base);
+                }
+            }
+            Value::Range(..) => {
+                self.warnings.insert(Warning::UnresolvedJump { at });
+            }
+        }
+    }
+
+    // Interprets the instruction at `at`, once for each opcode the
+    // program may have written there.
+    fn step(&mut self, at: usize) {
+        let base = self.bases[&at].0.clone();
+        // the instruction is code even if it turns out to be invalid
+        self.code.insert(at);
+        self.current = Some(at);
+        let opcodes: Vec<i64> = match self.code_cell(at, at) {
+            Some(Value::Set(s)) => {
+                let valid: Vec<i64> = s.into_iter().filter(|i| length(*i).is_some()).collect();
+                if valid.is_empty() {
+                    self.warnings.insert(Warning::InvalidInstruction { at });
+                }
+                valid
+            }
+            // the code was patched with unknown values: report the writes
+            // and fall back to the original instruction, if valid
+            Some(Value::Range(..)) => {
+                self.report_writes(at);
+                let original: Vec<i64> =
+                    self.program.get(at).copied().filter(|i| length(*i).is_some()).into_iter().collect();
+                if original.is_empty() {
+                    self.warnings.insert(Warning::UnresolvedInstruction { at });
+                }
+                original
+            }
+            None => {
+                self.warnings.insert(Warning::InvalidInstruction { at });
+                Vec::new()
+            }
+        };
+        for instr in opcodes {
+            self.execute(at, instr, &base);
+        }
+    }
+
+    fn execute(&mut self, at: usize, mut instr: i64, base: &Value) {
+        let op = instr % 100;
+        let len = length(instr).unwrap();
+        self.code.extend(at..at + len);
+        let span = self.spans.entry(at).or_insert(len);
+        *span = len.max(*span);
+        instr /= 100;
+        let mut addr = Vec::new();
+        for p in 0..len - 1 {
+            let param = self.code_cell(at, at + 1 + p).unwrap_or_else(|| Value::constant(0));
+            match self.address(at, instr % 10, &param, base) {
+                Some(a) => addr.push(a),
+                None => {
+                    self.warnings.insert(Warning::InvalidInstruction { at });
+                    return;
+                }
+            }
+            instr /= 10;
+        }
+
+        let next = at + len;
+        match op {
+            1 | 2 | 7 | 8 => {
+                let a = self.value(at, &addr[0]);
+                let b = self.value(at, &addr[1]);
+                let v = match op {
+                    1 => a.add(&b),
+                    2 => a.mul(&b),
+                    7 => a.less_than(&b),
+                    _ => a.equals(&b),
+                };
+                self.write(at, &addr[2], v);
+                self.flow(next, base);
+            }
+            3 => {
+                self.write(at, &addr[0], Value::top());
+                self.flow(next, base);
+            }
+            4 => {
+                self.value(at, &addr[0]);
+                self.flow(next, base);
+            }
+            5 | 6 => {
+                let cond = self.value(at, &addr[0]);
+                let target = self.value(at, &addr[1]);
+                let zero = cond.contains(0);
+                let nonzero = cond != Value::constant(0);
+                let (taken, not_taken) = if op == 5 { (nonzero, zero) } else { (zero, nonzero) };
+                if taken {
+                    self.jump(at, &target, base);
+                }
+                if not_taken {
+                    self.flow(next, base);
+                }
+            }
+            9 => {
+                let base = base.add(&self.value(at, &addr[0]));
+                if base.may_be_negative() {
+                    self.warnings.insert(Warning::NegativeBase { at });
+                }
+                self.flow(next, &base);
+            }
+            _ => {}
+        }
+    }
+}
+
+// The length of an instruction, None if it is invalid.
+fn length(instr: i64) -> Option<usize> {
+    match instr % 100 {
+        _ if instr < 0 => None,
+        1 | 2 | 7 | 8 => Some(4),
+        3 | 4 | 9 => Some(2),
+        5 | 6 => Some(3),
+        99 => Some(1),
+        _ => None,
+    }
+}
+
+pub fn analyze(program: &[i64]) -> Report {
+    let mut a = Analyzer {
+        program,
+        memory: HashMap::new(),
+        wild: HashMap::new(),
+        bases: HashMap::new(),
+        preds: HashMap::new(),
+        ancestors: HashMap::new(),
+        current: None,
+        todo: BTreeSet::new(),
+        code: BTreeSet::new(),
+        spans: BTreeMap::new(),
+        reads: BTreeSet::new(),
+        writes: BTreeSet::new(),
+        writers: HashMap::new(),
+        warnings: BTreeSet::new(),
+        changed: false,
+    };
+    a.flow(0, &Value::constant(0));
+
+    // iterate until the memory is stable
+    for _ in 0..MAX_ROUNDS {
+        a.changed = false;
+        while let Some(at) = a.todo.iter().next().copied() {
+            a.todo.remove(&at);
+            a.step(at);
+        }
+        if !a.changed {
+            break;
+        }
+        a.todo = a.bases.keys().copied().collect();
+    }
+
+    for (at, addr) in a.reads.iter() {
+        let written = a.memory.contains_key(addr)
+            || a.wild.values().any(|(lo, hi, _)| lo <= addr && addr <= hi);
+        if !written {
+            a.warnings.insert(Warning::UninitializedRead { at: *at, addr: *addr });
+        }
+    }
+    // only the writes into code that may run afterwards matter: code
+    // that already ran is often reused as data
+    let mut later = HashMap::new();
+    for (at, addr) in a.writes.iter() {
+        let later = later.entry(*at).or_insert_with(|| a.code_after(*at));
+        if later.contains(addr) {
+            a.warnings.insert(Warning::WriteToCode { at: *at, addr: *addr });
+        }
+    }
+    for (at, (lo, hi, _)) in a.wild.iter() {
+        let later = later.entry(*at).or_insert_with(|| a.code_after(*at));
+        if let Some(addr) = later.range(*lo..=*hi).next() {
+            a.warnings.insert(Warning::WriteToCode { at: *at, addr: *addr });
+        }
+    }
+
+    Report {
+        instructions: a.bases.keys().filter(|at| a.code.contains(at)).count(),
+        warnings: a.warnings.into_iter().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clean() {
+        // a function call through the stack, returning to a computed address
+        let program = [109,20,21101,9,0,0,1105,1,12,104,7,99,104,5,2106,0,0];
+        let report = analyze(&program);
+        assert_eq!(report.instructions, 7);
+        assert_eq!(report.warnings, vec![]);
+    }
+
+    #[test]
+    fn uninitialized() {
+        // reads cell 100, never written, and cell 50, written first
+        let program = [1101,1,2,50,1,50,100,51,4,51,99];
+        assert_eq!(analyze(&program).warnings, vec![Warning::UninitializedRead { at: 4, addr: 100 }]);
+    }
+
+    #[test]
+    fn self_modifying() {
+        assert_eq!(
+            analyze(&[1002,4,3,4,33]).warnings,
+            vec![Warning::WriteToCode { at: 0, addr: 4 }]
+        );
+    }
+
+    #[test]
+    fn patched() {
+        // day 5 patches an invalid opcode with the input: the analysis
+        // can't go further
+        let program = [3,225,1,225,6,6,1100,1,238,225,104,0,99];
+        let report = analyze(&program);
+        assert_eq!(
+            report.warnings,
+            vec![Warning::WriteToCode { at: 2, addr: 6 }, Warning::UnresolvedInstruction { at: 6 }]
+        );
+        assert!(!report.is_complete());
+
+        // the address of the output is patched just before: the original
+        // address (beyond the program) is never read
+        let program = [101,9,10,5,4,100,99,0,0,0,1];
+        let report = analyze(&program);
+        assert_eq!(report.instructions, 3);
+        assert_eq!(report.warnings, vec![Warning::WriteToCode { at: 0, addr: 5 }]);
+    }
+
+    #[test]
+    fn reused_code() {
+        // as in day 2, each instruction writes over code that already ran
+        let program = [1,0,0,3,1,1,2,3,2,3,11,0,99,4];
+        let report = analyze(&program);
+        assert_eq!(report.instructions, 4);
+        assert_eq!(report.warnings, vec![]);
+        assert!(report.is_complete());
+
+        // the output patched after it ran, then in a loop
+        assert_eq!(analyze(&[104,1,1101,7,0,1,99]).warnings, vec![]);
+        assert_eq!(analyze(&[104,1,1101,7,0,1,1105,1,0]).warnings, vec![Warning::WriteToCode { at: 2, addr: 1 }]);
+    }
+
+    #[test]
+    fn negative() {
+        // the relative base goes negative, then is used for a read
+        let program = [109,-5,204,2,3,20,5,20,20,99];
+        assert_eq!(
+            analyze(&program).warnings,
+            vec![
+                Warning::NegativeAddress { at: 2 },
+                Warning::NegativeBase { at: 0 },
+                Warning::NegativeJump { at: 6 },
+                Warning::UnresolvedJump { at: 6 },
+            ]
+        );
+
+        // the jump target comes from the input
+        let program = [3,10,105,1,10,99];
+        assert_eq!(
+            analyze(&program).warnings,
+            vec![
+                Warning::NegativeJump { at: 2 },
+                Warning::UnresolvedJump { at: 2 },
+            ]
+        );
+    }
+}
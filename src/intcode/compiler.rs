@@ -0,0 +1,914 @@
+// A compiler for a tiny imperative language, to write Intcode guest
+// programs without assembling them by hand.
+//
+// A program is a list of global variables and functions, starting
+// from `main`:
+//
+//     let table[10];          // a global array (zero-filled)
+//     let count = 3;          // a global integer
+//
+//     fn square(x) {
+//         return x * x;
+//     }
+//
+//     fn main() {
+//         let i = 0;
+//         while i < count {
+//             table[i] = square(read());
+//             write(table[i]);
+//             i = i + 1;
+//         }
+//     }
+//
+// All values are integers. The operators are `+ - *`, the comparisons
+// `< <= > >= == !=` and the logical `! && ||` (both sides are always
+// evaluated), and the operands are evaluated from left to right.
+// `read()` takes a value from the input, `write(x)` sends one to the
+// output. The keywords cannot be used as names. Arrays are global and
+// unchecked: they are indexed by patching the address into the next
+// instruction.
+//
+// Functions run on a stack of frames, addressed through the relative
+// base: the return address is at offset 0, followed by the arguments,
+// the local variables and the temporaries. The caller builds the frame
+// of the callee above its own, and the result comes back in a fixed
+// cell.
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Num(i64),
+    Ident(String),
+    Sym(&'static str),
+}
+
+const KEYWORDS: [&str; 8] = ["let", "fn", "if", "else", "while", "return", "read", "write"];
+
+const SYMBOLS: [&str; 21] = [
+    "<=", ">=", "==", "!=", "&&", "||", "(", ")", "{", "}", "[", "]", ",", ";", "=", "+", "-", "*", "<", ">", "!",
+];
+
+// Splits the source into tokens, with their line numbers.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut tokens = Vec::new();
+    for (n, line) in source.lines().enumerate() {
+        let line = line.split("//").next().unwrap();
+        let mut rest = line.trim_start();
+        while !rest.is_empty() {
+            let c = rest.chars().next().unwrap();
+            let len = if c.is_ascii_digit() {
+                let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                let v = rest[..len].parse().map_err(|_| format!("line {}: number too large", n + 1))?;
+                tokens.push((Token::Num(v), n + 1));
+                len
+            } else if c.is_ascii_alphabetic() || c == '_' {
+                let len = rest.find(|c: char| !c.is_ascii_alphanumeric() && c != '_').unwrap_or(rest.len());
+                tokens.push((Token::Ident(rest[..len].to_string()), n + 1));
+                len
+            } else {
+                match SYMBOLS.iter().find(|s| rest.starts_with(*s)) {
+                    Some(s) => {
+                        tokens.push((Token::Sym(s), n + 1));
+                        s.len()
+                    }
+                    None => return Err(format!("line {}: unexpected character '{}'", n + 1, c)),
+                }
+            };
+            rest = rest[len..].trim_start();
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    And,
+    Or,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Num(i64),
+    Var(String),
+    Index(String, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Read,
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn has_call(&self) -> bool {
+        match self {
+            Expr::Call(..) => true,
+            Expr::Num(_) | Expr::Var(_) | Expr::Read => false,
+            Expr::Index(_, e) | Expr::Neg(e) | Expr::Not(e) => e.has_call(),
+            Expr::Binary(_, a, b) => a.has_call() || b.has_call(),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Stmt {
+    Let(String, Option<Expr>),
+    Assign(Expr, Expr),
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    While(Expr, Vec<Stmt>),
+    Return(Option<Expr>),
+    Write(Expr),
+    Expr(Expr),
+}
+
+struct Function {
+    name: String,
+    params: Vec<String>,
+    body: Vec<Stmt>,
+}
+
+struct Global {
+    name: String,
+    size: Option<usize>, // the length of arrays
+    init: i64,
+}
+
+const MAX_ARRAY: i64 = 1 << 20; // the largest array, in cells
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn error(&self, msg: &str) -> String {
+        match self.tokens.get(self.pos) {
+            Some((_, line)) => format!("line {}: {}", line, msg),
+            None => format!("at the end: {}", msg),
+        }
+    }
+
+    fn is(&self, sym: &str) -> bool {
+        matches!(self.peek(), Some(Token::Sym(s)) if *s == sym)
+    }
+
+    fn is_keyword(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(w)) if w == word)
+    }
+
+    // Consumes `sym` if it is next.
+    fn accept(&mut self, sym: &str) -> bool {
+        let found = self.is(sym) || self.is_keyword(sym);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, sym: &str) -> Result<(), String> {
+        if self.accept(sym) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", sym)))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        match self.peek() {
+            Some(Token::Ident(name)) if KEYWORDS.contains(&name.as_str()) => {
+                Err(self.error(&format!("'{}' is a keyword", name)))
+            }
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.pos += 1;
+                Ok(name)
+            }
+            _ => Err(self.error("expected a name")),
+        }
+    }
+
+    fn number(&mut self) -> Result<i64, String> {
+        let sign = if self.accept("-") { -1 } else { 1 };
+        match self.peek() {
+            Some(Token::Num(v)) => {
+                let v = *v;
+                self.pos += 1;
+                Ok(sign * v)
+            }
+            _ => Err(self.error("expected a number")),
+        }
+    }
+
+    fn program(&mut self) -> Result<(Vec<Global>, Vec<Function>), String> {
+        let mut globals = Vec::new();
+        let mut functions = Vec::new();
+        while self.peek().is_some() {
+            if self.accept("let") {
+                let name = self.ident()?;
+                let mut global = Global { name, size: None, init: 0 };
+                if self.accept("[") {
+                    let size = self.number()?;
+                    if !(1..=MAX_ARRAY).contains(&size) {
+                        return Err(self.error(&format!("array size must be from 1 to {}", MAX_ARRAY)));
+                    }
+                    global.size = Some(size as usize);
+                    self.expect("]")?;
+                } else if self.accept("=") {
+                    global.init = self.number()?;
+                }
+                self.expect(";")?;
+                globals.push(global);
+            } else if self.accept("fn") {
+                let name = self.ident()?;
+                self.expect("(")?;
+                let mut params = Vec::new();
+                while !self.accept(")") {
+                    if !params.is_empty() {
+                        self.expect(",")?;
+                    }
+                    params.push(self.ident()?);
+                }
+                let body = self.block()?;
+                functions.push(Function { name, params, body });
+            } else {
+                return Err(self.error("expected 'let' or 'fn'"));
+            }
+        }
+        Ok((globals, functions))
+    }
+
+    fn block(&mut self) -> Result<Vec<Stmt>, String> {
+        self.expect("{")?;
+        let mut stmts = Vec::new();
+        while !self.accept("}") {
+            stmts.push(self.statement()?);
+        }
+        Ok(stmts)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, String> {
+        let stmt = if self.accept("let") {
+            let name = self.ident()?;
+            let init = if self.accept("=") { Some(self.expr()?) } else { None };
+            Stmt::Let(name, init)
+        } else if self.accept("if") {
+            let cond = self.expr()?;
+            let then = self.block()?;
+            let otherwise = if !self.accept("else") {
+                Vec::new()
+            } else if self.is_keyword("if") {
+                vec![self.statement()?]
+            } else {
+                self.block()?
+            };
+            return Ok(Stmt::If(cond, then, otherwise));
+        } else if self.accept("while") {
+            let cond = self.expr()?;
+            return Ok(Stmt::While(cond, self.block()?));
+        } else if self.accept("return") {
+            Stmt::Return(if self.is(";") { None } else { Some(self.expr()?) })
+        } else if self.accept("write") {
+            self.expect("(")?;
+            let e = self.expr()?;
+            self.expect(")")?;
+            Stmt::Write(e)
+        } else {
+            let e = self.expr()?;
+            if self.accept("=") {
+                if !matches!(e, Expr::Var(_) | Expr::Index(..)) {
+                    return Err(self.error("cannot assign to an expression"));
+                }
+                Stmt::Assign(e, self.expr()?)
+            } else {
+                Stmt::Expr(e)
+            }
+        };
+        self.expect(";")?;
+        Ok(stmt)
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        self.binary(0)
+    }
+
+    // Parses the binary operators by precedence, from the lowest.
+    fn binary(&mut self, level: usize) -> Result<Expr, String> {
+        const LEVELS: [&[(&str, BinOp)]; 5] = [
+            &[("||", BinOp::Or)],
+            &[("&&", BinOp::And)],
+            &[("==", BinOp::Eq), ("!=", BinOp::Ne)],
+            &[("<=", BinOp::Le), (">=", BinOp::Ge), ("<", BinOp::Lt), (">", BinOp::Gt)],
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+        ];
+        if level == LEVELS.len() {
+            return self.product();
+        }
+        let mut left = self.binary(level + 1)?;
+        while let Some((_, op)) = LEVELS[level].iter().find(|(s, _)| self.is(s)) {
+            self.pos += 1;
+            let right = self.binary(level + 1)?;
+            left = Expr::Binary(*op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn product(&mut self) -> Result<Expr, String> {
+        let mut left = self.unary()?;
+        while self.accept("*") {
+            left = Expr::Binary(BinOp::Mul, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.accept("-") {
+            Ok(Expr::Neg(Box::new(self.unary()?)))
+        } else if self.accept("!") {
+            Ok(Expr::Not(Box::new(self.unary()?)))
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, String> {
+        if self.accept("(") {
+            let e = self.expr()?;
+            self.expect(")")?;
+            return Ok(e);
+        }
+        if let Some(Token::Num(v)) = self.peek() {
+            let v = *v;
+            self.pos += 1;
+            return Ok(Expr::Num(v));
+        }
+        if self.accept("read") {
+            self.expect("(")?;
+            self.expect(")")?;
+            return Ok(Expr::Read);
+        }
+        let name = self.ident()?;
+        if self.accept("(") {
+            let mut args = Vec::new();
+            while !self.accept(")") {
+                if !args.is_empty() {
+                    self.expect(",")?;
+                }
+                args.push(self.expr()?);
+            }
+            Ok(Expr::Call(name, args))
+        } else if self.accept("[") {
+            let index = self.expr()?;
+            self.expect("]")?;
+            Ok(Expr::Index(name, Box::new(index)))
+        } else {
+            Ok(Expr::Var(name))
+        }
+    }
+}
+
+// Addresses only known once all the code is generated.
+#[derive(Clone, Copy, Debug)]
+enum Symbol {
+    Label(usize),
+    Global(usize), // offset in the data after the code
+    Stack,         // the first cell after the data
+}
+
+// An instruction parameter. When there is a symbol, its address is
+// added to the value.
+#[derive(Clone, Copy, Debug)]
+struct Operand {
+    mode: i64,
+    value: i64,
+    symbol: Option<Symbol>,
+}
+
+fn imm(value: i64) -> Operand {
+    Operand { mode: 1, value, symbol: None }
+}
+
+fn rel(offset: i64) -> Operand {
+    Operand { mode: 2, value: offset, symbol: None }
+}
+
+fn pos(addr: usize) -> Operand {
+    Operand { mode: 0, value: addr as i64, symbol: None }
+}
+
+// the contents (mode 0) or the address (mode 1) of a symbol
+fn sym(mode: i64, symbol: Symbol, offset: i64) -> Operand {
+    Operand { mode, value: offset, symbol: Some(symbol) }
+}
+
+const RET: Symbol = Symbol::Global(0); // the result of the last call
+
+struct Codegen {
+    code: Vec<i64>,
+    fixups: Vec<(usize, Symbol)>,
+    labels: Vec<Option<usize>>,
+    data: Vec<i64>,
+    globals: HashMap<String, (usize, Option<usize>)>, // offset and array length
+    functions: HashMap<String, (usize, usize)>,       // label and number of parameters
+    scopes: Vec<Vec<(String, i64)>>,                  // local variables and their slot
+    depth: i64,                                       // the first free slot in the frame
+    function: String,
+}
+
+impl Codegen {
+    fn emit(&mut self, op: i64, operands: &[Operand]) {
+        let modes: i64 = operands.iter().rev().fold(0, |m, o| 10 * m + o.mode);
+        self.code.push(op + 100 * modes);
+        for o in operands {
+            if let Some(s) = o.symbol {
+                self.fixups.push((self.code.len(), s));
+            }
+            self.code.push(o.value);
+        }
+    }
+
+    fn label(&mut self) -> usize {
+        self.labels.push(None);
+        self.labels.len() - 1
+    }
+
+    fn place(&mut self, label: usize) {
+        self.labels[label] = Some(self.code.len());
+    }
+
+    fn temp(&mut self) -> Operand {
+        self.depth += 1;
+        rel(self.depth - 1)
+    }
+
+    fn error(&self, msg: String) -> String {
+        format!("in function '{}': {}", self.function, msg)
+    }
+
+    fn copy(&mut self, from: Operand, to: Operand) {
+        self.emit(1, &[from, imm(0), to]);
+    }
+
+    fn jump(&mut self, label: usize) {
+        self.emit(5, &[imm(1), sym(1, Symbol::Label(label), 0)]);
+    }
+
+    fn jump_if_false(&mut self, cond: Operand, label: usize) {
+        self.emit(6, &[cond, sym(1, Symbol::Label(label), 0)]);
+    }
+
+    fn ret(&mut self, value: Operand) {
+        self.copy(value, sym(0, RET, 0));
+        self.emit(6, &[imm(0), rel(0)]);
+    }
+
+    fn variable(&self, name: &str) -> Result<Operand, String> {
+        for scope in self.scopes.iter().rev() {
+            if let Some((_, slot)) = scope.iter().rev().find(|(n, _)| n == name) {
+                return Ok(rel(*slot));
+            }
+        }
+        match self.globals.get(name) {
+            Some((offset, None)) => Ok(sym(0, Symbol::Global(*offset), 0)),
+            Some(_) => Err(self.error(format!("'{}' is an array", name))),
+            None => Err(self.error(format!("unknown variable '{}'", name))),
+        }
+    }
+
+    fn array(&self, name: &str) -> Result<usize, String> {
+        match self.globals.get(name) {
+            Some((offset, Some(_))) => Ok(*offset),
+            _ => Err(self.error(format!("unknown array '{}'", name))),
+        }
+    }
+
+    fn function(&mut self, f: &Function) -> Result<(), String> {
+        self.function = f.name.clone();
+        self.place(self.functions[&f.name].0);
+        self.scopes = vec![f.params.iter().cloned().zip(1..).collect()];
+        self.depth = f.params.len() as i64 + 1;
+        self.block(&f.body)?;
+        self.ret(imm(0));
+        Ok(())
+    }
+
+    fn block(&mut self, stmts: &[Stmt]) -> Result<(), String> {
+        let depth = self.depth;
+        self.scopes.push(Vec::new());
+        for s in stmts {
+            self.statement(s)?;
+        }
+        self.scopes.pop();
+        self.depth = depth;
+        Ok(())
+    }
+
+    fn statement(&mut self, stmt: &Stmt) -> Result<(), String> {
+        // the temporaries are released at the end of each statement
+        let depth = self.depth;
+        match stmt {
+            Stmt::Let(name, init) => {
+                let slot = self.temp();
+                if let Some(e) = init {
+                    let v = self.expr(e)?;
+                    self.copy(v, slot);
+                } else {
+                    self.copy(imm(0), slot);
+                }
+                self.scopes.last_mut().unwrap().push((name.clone(), slot.value));
+                self.depth = depth + 1;
+                return Ok(());
+            }
+            Stmt::Assign(Expr::Index(name, index), e) => {
+                let array = self.array(name)?;
+                let index = self.expr(index)?;
+                let index = self.keep(index, e);
+                let v = self.expr(e)?;
+                match index {
+                    Operand { mode: 1, value, symbol: None } => {
+                        self.copy(v, sym(0, Symbol::Global(array), value));
+                    }
+                    index => {
+                        // patch the destination of the copy that follows
+                        let dest = self.code.len() + 4 + 3;
+                        self.emit(1, &[sym(1, Symbol::Global(array), 0), index, pos(dest)]);
+                        self.copy(v, pos(0));
+                    }
+                }
+            }
+            Stmt::Assign(Expr::Var(name), e) => {
+                let var = self.variable(name)?;
+                let v = self.expr(e)?;
+                self.copy(v, var);
+            }
+            Stmt::Assign(..) => unreachable!(),
+            Stmt::If(cond, then, otherwise) => {
+                let cond = self.expr(cond)?;
+                let (l_else, l_end) = (self.label(), self.label());
+                self.jump_if_false(cond, l_else);
+                self.depth = depth;
+                self.block(then)?;
+                if !otherwise.is_empty() {
+                    self.jump(l_end);
+                }
+                self.place(l_else);
+                self.block(otherwise)?;
+                self.place(l_end);
+            }
+            Stmt::While(cond, body) => {
+                let (l_top, l_end) = (self.label(), self.label());
+                self.place(l_top);
+                let cond = self.expr(cond)?;
+                self.jump_if_false(cond, l_end);
+                self.depth = depth;
+                self.block(body)?;
+                self.jump(l_top);
+                self.place(l_end);
+            }
+            Stmt::Return(e) => {
+                let v = match e {
+                    Some(e) => self.expr(e)?,
+                    None => imm(0),
+                };
+                self.ret(v);
+            }
+            Stmt::Write(e) => {
+                let v = self.expr(e)?;
+                self.emit(4, &[v]);
+            }
+            Stmt::Expr(e) => {
+                self.expr(e)?;
+            }
+        }
+        self.depth = depth;
+        Ok(())
+    }
+
+    fn expr(&mut self, e: &Expr) -> Result<Operand, String> {
+        Ok(match e {
+            Expr::Num(v) => imm(*v),
+            Expr::Var(name) => self.variable(name)?,
+            Expr::Index(name, index) => {
+                let array = self.array(name)?;
+                match self.expr(index)? {
+                    Operand { mode: 1, value, symbol: None } => sym(0, Symbol::Global(array), value),
+                    index => {
+                        // patch the source of the copy that follows
+                        let t = self.temp();
+                        let src = self.code.len() + 4 + 1;
+                        self.emit(1, &[sym(1, Symbol::Global(array), 0), index, pos(src)]);
+                        self.copy(pos(0), t);
+                        t
+                    }
+                }
+            }
+            Expr::Call(name, args) => {
+                let (label, arity) = match self.functions.get(name) {
+                    Some(f) => *f,
+                    None => return Err(self.error(format!("unknown function '{}'", name))),
+                };
+                if args.len() != arity {
+                    return Err(self.error(format!("'{}' takes {} arguments, {} given", name, arity, args.len())));
+                }
+                // the frame of the callee starts at the first free slot
+                let frame = self.depth;
+                self.depth += 1 + arity as i64;
+                for (i, arg) in args.iter().enumerate() {
+                    let v = self.expr(arg)?;
+                    self.copy(v, rel(frame + 1 + i as i64));
+                    self.depth = frame + 1 + arity as i64;
+                }
+                let back = self.label();
+                self.copy(sym(1, Symbol::Label(back), 0), rel(frame));
+                self.emit(9, &[imm(frame)]);
+                self.jump(label);
+                self.place(back);
+                self.emit(9, &[imm(-frame)]);
+                self.depth = frame;
+                let t = self.temp();
+                self.copy(sym(0, RET, 0), t);
+                t
+            }
+            Expr::Read => {
+                let t = self.temp();
+                self.emit(3, &[t]);
+                t
+            }
+            Expr::Neg(e) => match self.expr(e)? {
+                Operand { mode: 1, value, symbol: None } => imm(-value),
+                v => {
+                    let t = self.temp();
+                    self.emit(2, &[v, imm(-1), t]);
+                    t
+                }
+            },
+            Expr::Not(e) => {
+                let v = self.expr(e)?;
+                let t = self.temp();
+                self.emit(8, &[v, imm(0), t]);
+                t
+            }
+            Expr::Binary(op, a, b) => {
+                let a = self.expr(a)?;
+                let a = self.keep(a, b);
+                let b = self.expr(b)?;
+                self.binary(*op, a, b)
+            }
+        })
+    }
+
+    // Copies the value of a global (a variable or an array cell) to a
+    // temporary if `later` makes a call, which could change it before it
+    // is used.
+    fn keep(&mut self, v: Operand, later: &Expr) -> Operand {
+        if v.mode == 0 && later.has_call() {
+            let t = self.temp();
+            self.copy(v, t);
+            t
+        } else {
+            v
+        }
+    }
+
+    fn binary(&mut self, op: BinOp, a: Operand, b: Operand) -> Operand {
+        let t = self.temp();
+        match op {
+            BinOp::Add => self.emit(1, &[a, b, t]),
+            BinOp::Mul => self.emit(2, &[a, b, t]),
+            BinOp::Sub => {
+                self.emit(2, &[b, imm(-1), t]);
+                self.emit(1, &[a, t, t]);
+            }
+            BinOp::Lt => self.emit(7, &[a, b, t]),
+            BinOp::Gt => self.emit(7, &[b, a, t]),
+            BinOp::Le => {
+                self.emit(7, &[b, a, t]);
+                self.emit(8, &[t, imm(0), t]);
+            }
+            BinOp::Ge => {
+                self.emit(7, &[a, b, t]);
+                self.emit(8, &[t, imm(0), t]);
+            }
+            BinOp::Eq => self.emit(8, &[a, b, t]),
+            BinOp::Ne => {
+                self.emit(8, &[a, b, t]);
+                self.emit(8, &[t, imm(0), t]);
+            }
+            BinOp::And | BinOp::Or => {
+                // with both sides turned into "is zero" flags
+                let u = self.temp();
+                self.emit(8, &[a, imm(0), t]);
+                self.emit(8, &[b, imm(0), u]);
+                self.emit(if op == BinOp::And { 1 } else { 2 }, &[t, u, t]);
+                self.emit(8, &[t, imm(0), t]);
+            }
+        }
+        t
+    }
+}
+
+// Compiles a program, returning the Intcode image or a description of
+// the first error found.
+pub fn compile(source: &str) -> Result<Vec<i64>, String> {
+    let mut parser = Parser { tokens: tokenize(source)?, pos: 0 };
+    let (globals, functions) = parser.program()?;
+
+    let mut gen = Codegen {
+        code: Vec::new(),
+        fixups: Vec::new(),
+        labels: Vec::new(),
+        data: vec![0], // RET
+        globals: HashMap::new(),
+        functions: HashMap::new(),
+        scopes: Vec::new(),
+        depth: 0,
+        function: String::new(),
+    };
+    for g in globals.iter() {
+        if gen.globals.insert(g.name.clone(), (gen.data.len(), g.size)).is_some() {
+            return Err(format!("'{}' is declared twice", g.name));
+        }
+        match g.size {
+            Some(n) => gen.data.resize(gen.data.len() + n, 0),
+            None => gen.data.push(g.init),
+        }
+    }
+    for f in functions.iter() {
+        let label = gen.label();
+        if gen.functions.insert(f.name.clone(), (label, f.params.len())).is_some() {
+            return Err(format!("function '{}' is defined twice", f.name));
+        }
+    }
+    let main = match gen.functions.get("main") {
+        Some((label, 0)) => *label,
+        Some(_) => return Err("'main' cannot take arguments".to_string()),
+        None => return Err("no 'main' function".to_string()),
+    };
+
+    // set up the stack, then call main and halt
+    let halt = gen.label();
+    gen.emit(9, &[sym(1, Symbol::Stack, 0)]);
+    gen.copy(sym(1, Symbol::Label(halt), 0), rel(0));
+    gen.jump(main);
+    gen.place(halt);
+    gen.emit(99, &[]);
+    for f in functions.iter() {
+        gen.function(f)?;
+    }
+
+    let code_len = gen.code.len();
+    let stack = code_len + gen.data.len();
+    let mut program = gen.code;
+    for (at, symbol) in gen.fixups {
+        program[at] += match symbol {
+            Symbol::Label(l) => gen.labels[l].unwrap(),
+            Symbol::Global(offset) => code_len + offset,
+            Symbol::Stack => stack,
+        } as i64;
+    }
+    program.extend(gen.data);
+    Ok(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::IntCode;
+
+    fn run(source: &str, input: &[i64]) -> Vec<i64> {
+        let mut c = IntCode::new(&compile(source).unwrap());
+        c.input.extend(input);
+        c.run();
+        assert!(c.is_halted());
+        c.output
+    }
+
+    #[test]
+    fn comparisons() {
+        // the comparison programs of day 5
+        let equal = "fn main() { write(read() == 8); }";
+        let less = "fn main() { write(read() < 8); }";
+        let nonzero = "fn main() { if read() != 0 { write(1); } else { write(0); } }";
+        for (i, expected) in [(7, 0), (8, 1), (9, 0)].iter() {
+            assert_eq!(run(equal, &[*i]), vec![*expected]);
+        }
+        for (i, expected) in [(7, 1), (8, 0), (9, 0)].iter() {
+            assert_eq!(run(less, &[*i]), vec![*expected]);
+        }
+        for (i, expected) in [(0, 0), (1, 1), (2, 1)].iter() {
+            assert_eq!(run(nonzero, &[*i]), vec![*expected]);
+        }
+        let compare = include_str!("../../examples/intcode/compare.ic");
+        for (i, expected) in [(5, 999), (8, 1000), (15, 1001)].iter() {
+            assert_eq!(run(compare, &[*i]), vec![*expected]);
+        }
+    }
+
+    #[test]
+    fn operators() {
+        let source = "
+            fn main() {
+                let a = read();
+                let b = read();
+                write(a + b); write(a - b); write(a * b); write(-a);
+                write(a < b); write(a <= b); write(a > b); write(a >= b);
+                write(a == b); write(a != b); write(!a);
+                write(a && b); write(a || b); write(a && 0); write(0 || 0);
+            }";
+        assert_eq!(run(source, &[3, 5]), vec![8, -2, 15, -3, 1, 1, 0, 0, 0, 1, 0, 1, 1, 0, 0]);
+        assert_eq!(run(source, &[5, 5]), vec![10, 0, 25, -5, 0, 1, 0, 1, 1, 0, 0, 1, 1, 0, 0]);
+    }
+
+    #[test]
+    fn functions() {
+        let factorial = include_str!("../../examples/intcode/factorial.ic");
+        assert_eq!(run(factorial, &[0]), vec![1]);
+        assert_eq!(run(factorial, &[10]), vec![3628800]);
+
+        // locals are kept across calls, nested calls in arguments
+        let source = "
+            let calls;
+            fn add(a, b) { calls = calls + 1; return a + b; }
+            fn main() {
+                let x = 1;
+                write(add(x, add(2, add(3, 4))) * 2);
+                write(x);
+                write(calls);
+            }";
+        assert_eq!(run(source, &[]), vec![20, 1, 3]);
+    }
+
+    #[test]
+    fn arrays() {
+        let sort = include_str!("../../examples/intcode/sort.ic");
+        assert_eq!(run(sort, &[5, 3, -1, 4, 1, 5]), vec![-1, 1, 3, 4, 5]);
+        let primes = include_str!("../../examples/intcode/primes.ic");
+        assert_eq!(run(primes, &[30]), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+    }
+
+    #[test]
+    fn scopes() {
+        let source = "
+            let x = 7;
+            fn main() {
+                write(x);
+                let x = 1;
+                if 1 { let x = 2; write(x); }
+                write(x);
+                let y = x + 1;
+                write(y);
+            }";
+        assert_eq!(run(source, &[]), vec![7, 2, 1, 2]);
+    }
+
+    #[test]
+    fn evaluation_order() {
+        // the operands are read from left to right, before the calls on
+        // their right change them
+        let source = "
+            let x = 1;
+            let i;
+            let a[2];
+            fn f() { x = 10; a[0] = 5; return 0; }
+            fn g() { i = 1; return 7; }
+            fn main() {
+                write(x + f());
+                write(f() + x);
+                a[0] = 2;
+                write(a[0] * (f() + 1));
+                a[i] = g();
+                write(a[0]);
+                write(a[1]);
+            }";
+        assert_eq!(run(source, &[]), vec![1, 10, 2, 7, 0]);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(compile("fn f() {}"), Err("no 'main' function".to_string()));
+        assert_eq!(compile("fn main() {\n  write(1)\n}"), Err("line 3: expected ';'".to_string()));
+        assert_eq!(compile("fn main() { x = 1; }"), Err("in function 'main': unknown variable 'x'".to_string()));
+        assert_eq!(compile("fn main() { 1 = 2; }"), Err("line 1: cannot assign to an expression".to_string()));
+        assert_eq!(compile("fn main() { # }"), Err("line 1: unexpected character '#'".to_string()));
+        assert_eq!(compile("let a[-1];"), Err("line 1: array size must be from 1 to 1048576".to_string()));
+        assert_eq!(compile("let a[0];"), Err("line 1: array size must be from 1 to 1048576".to_string()));
+        assert_eq!(compile("fn main() { let while = 1; }"), Err("line 1: 'while' is a keyword".to_string()));
+        assert_eq!(compile("let x; fn write(a) {}"), Err("line 1: 'write' is a keyword".to_string()));
+        assert_eq!(compile("fn main() { write(read); }"), Err("line 1: expected '('".to_string()));
+        assert_eq!(
+            compile("fn f(a) {} fn main() { f(); }"),
+            Err("in function 'main': 'f' takes 1 arguments, 0 given".to_string())
+        );
+    }
+}
@@ -0,0 +1,205 @@
+// A conformance suite for Intcode interpreters: named cases for each
+// opcode and parameter mode, the relative base, self-modifying code
+// and large numbers. Every case can be checked against any type
+// implementing `Machine`.
+use super::Machine;
+
+pub struct Case {
+    pub name: &'static str,
+    pub program: Vec<i64>,
+    pub input: Vec<i64>,
+    pub output: Vec<i64>,          // expected output
+    pub memory: Vec<(usize, i64)>, // expected memory cells at the end
+    pub halted: bool,              // false if the program should block on input
+}
+
+impl Case {
+    fn new(name: &'static str, program: &[i64], input: &[i64], output: &[i64]) -> Case {
+        Case {
+            name,
+            program: program.to_vec(),
+            input: input.to_vec(),
+            output: output.to_vec(),
+            memory: Vec::new(),
+            halted: true,
+        }
+    }
+
+    fn with_memory(mut self, memory: &[(usize, i64)]) -> Case {
+        self.memory = memory.to_vec();
+        self
+    }
+
+    // expects the whole memory to be `image` at the end
+    fn with_image(self, image: &[i64]) -> Case {
+        let memory: Vec<_> = image.iter().copied().enumerate().collect();
+        self.with_memory(&memory)
+    }
+
+    fn blocked(mut self) -> Case {
+        self.halted = false;
+        self
+    }
+
+    // Runs the case on a machine loaded with `program`, returning
+    // a description of the first mismatch found.
+    pub fn check<M: Machine>(&self, machine: &mut M) -> Result<(), String> {
+        for v in self.input.iter() {
+            machine.push_input(*v);
+        }
+        machine.run();
+
+        let output = machine.take_output();
+        if output != self.output {
+            return Err(format!("output {:?}, expected {:?}", output, self.output));
+        }
+        if machine.is_halted() != self.halted {
+            return Err(format!("halted: {}, expected {}", machine.is_halted(), self.halted));
+        }
+        for (addr, value) in self.memory.iter() {
+            let v = machine.read(*addr);
+            if v != *value {
+                return Err(format!("memory[{}] = {}, expected {}", addr, v, value));
+            }
+        }
+        if self.halted {
+            // running a halted machine must be a no-op
+            machine.run();
+            let output = machine.take_output();
+            if !output.is_empty() || !machine.is_halted() {
+                return Err(format!("resumed after halting, output {:?}", output));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn cases() -> Vec<Case> {
+    vec![
+        // addition and multiplication in every parameter mode
+        Case::new("add/position", &[1,5,6,7,99,20,22,0], &[], &[]).with_memory(&[(7, 42)]),
+        Case::new("add/immediate", &[1101,20,22,5,99,0], &[], &[]).with_memory(&[(5, 42)]),
+        Case::new("add/relative", &[109,10,22201,0,1,2,99,0,0,0,20,22,0], &[], &[]).with_memory(&[(12, 42)]),
+        Case::new("add/mixed", &[1001,6,-2,7,99,0,44,0], &[], &[]).with_memory(&[(7, 42)]),
+        Case::new("add/negative", &[1101,-50,8,5,99,0], &[], &[]).with_memory(&[(5, -42)]),
+        Case::new("mul/position", &[2,5,6,7,99,6,7,0], &[], &[]).with_memory(&[(7, 42)]),
+        Case::new("mul/immediate", &[1102,6,7,5,99,0], &[], &[]).with_memory(&[(5, 42)]),
+        Case::new("mul/relative", &[109,10,22202,0,1,2,99,0,0,0,6,7,0], &[], &[]).with_memory(&[(12, 42)]),
+        Case::new("mul/negative", &[1102,-6,7,5,99,0], &[], &[]).with_memory(&[(5, -42)]),
+
+        // input and output
+        Case::new("input/position", &[3,3,99,0], &[42], &[]).with_memory(&[(3, 42)]),
+        Case::new("input/relative", &[109,6,203,-1,99,0,0], &[42], &[]).with_memory(&[(5, 42)]),
+        Case::new("input/fifo", &[3,9,3,10,4,9,4,10,99,0,0], &[1, 2], &[1, 2]),
+        Case::new("input/blocks when empty", &[104,1,3,7,4,7,99,0], &[], &[1]).blocked(),
+        Case::new("output/position", &[4,3,99,42], &[], &[42]),
+        Case::new("output/immediate", &[104,42,99], &[], &[42]),
+        Case::new("output/relative", &[109,3,204,1,99], &[], &[99]),
+        Case::new("output/echo", &[3,0,4,0,99], &[123], &[123]),
+
+        // jumps
+        Case::new("jump-if-true/taken", &[1105,1,4,99,104,1,99], &[], &[1]),
+        Case::new("jump-if-true/not taken", &[1105,0,7,104,0,99,0,104,1,99], &[], &[0]),
+        Case::new("jump-if-false/taken", &[1106,0,7,104,0,99,0,104,1,99], &[], &[1]),
+        Case::new("jump-if-false/not taken", &[1106,5,7,104,0,99,0,104,1,99], &[], &[0]),
+        Case::new("jump/position condition", &[1005,7,8,104,0,99,0,1,104,1,99], &[], &[1]),
+        Case::new("jump/relative target", &[109,7,2105,1,2,99,0,0,0,10,104,1,99], &[], &[1]),
+        Case::new("jump/countdown loop", &[4,13,1001,13,-1,13,1005,13,0,99,0,0,0,3], &[], &[3, 2, 1])
+            .with_memory(&[(13, 0)]),
+
+        // comparisons
+        Case::new("less-than/true", &[1107,1,2,5,99,-1], &[], &[]).with_memory(&[(5, 1)]),
+        Case::new("less-than/false", &[1107,2,2,5,99,-1], &[], &[]).with_memory(&[(5, 0)]),
+        Case::new("less-than/negative", &[1107,-3,-2,5,99,-1], &[], &[]).with_memory(&[(5, 1)]),
+        Case::new("less-than/relative", &[109,4,21107,1,2,3,99,-1], &[], &[]).with_memory(&[(7, 1)]),
+        Case::new("equals/true", &[1108,7,7,5,99,-1], &[], &[]).with_memory(&[(5, 1)]),
+        Case::new("equals/false", &[1108,7,8,5,99,-1], &[], &[]).with_memory(&[(5, 0)]),
+        Case::new("equals/position", &[8,5,6,7,99,9,9,-1], &[], &[]).with_memory(&[(7, 1)]),
+
+        // relative base
+        Case::new("base/starts at zero", &[204,0,99], &[], &[204]),
+        Case::new("base/accumulates", &[109,2,109,3,204,-5,99], &[], &[109]),
+        Case::new("base/negative adjustment", &[109,10,109,-7,204,0,99], &[], &[-7]),
+        Case::new("base/adjust by position", &[9,5,204,0,99,4], &[], &[99]),
+        Case::new("base/adjust by relative", &[109,7,209,0,204,-9,99,2], &[], &[109]),
+        Case::new("base/call and return", &[109,20,21101,9,0,0,1105,1,12,104,7,99,104,5,2106,0,0], &[], &[5, 7]),
+        Case::new("base/beyond image", &[109,1000,21101,3,4,0,204,0,99], &[], &[7]).with_memory(&[(1000, 7)]),
+
+        // memory outside the program image
+        Case::new("memory/read beyond image", &[4,100,99], &[], &[0]),
+        Case::new("memory/write beyond image", &[1101,1,2,50,4,50,99], &[], &[3]).with_memory(&[(50, 3)]),
+        Case::new("memory/relative read beyond image", &[109,50,204,50,99], &[], &[0]),
+
+        // self-modifying code
+        Case::new("self-modifying/patch opcode", &[1002,4,3,4,33], &[], &[]).with_memory(&[(4, 99)]),
+        Case::new("self-modifying/patch operand", &[1101,40,2,5,104,0,99], &[], &[42]),
+        Case::new("self-modifying/write instruction", &[1101,100,4,4,99,42,99], &[], &[42]),
+        Case::new("self-modifying/input into code", &[3,3,104,0,99], &[42], &[42]),
+        Case::new("self-modifying/quine", &[109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99], &[],
+            &[109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99]),
+
+        // large numbers
+        Case::new("large/multiplication", &[1102,34915192,34915192,7,4,7,99,0], &[], &[1219070632396864]),
+        Case::new("large/immediate", &[104,1125899906842624,99], &[], &[1125899906842624]),
+        Case::new("large/near maximum", &[1101,i64::MAX - 1,1,5,99,0], &[], &[]).with_memory(&[(5, i64::MAX)]),
+        Case::new("large/negative product", &[1102,-2147483648,2147483648,5,99,0], &[], &[])
+            .with_memory(&[(5, -4611686018427387904)]),
+        Case::new("large/compare extremes", &[1107,i64::MIN,i64::MAX,5,99,-1], &[], &[]).with_memory(&[(5, 1)]),
+
+        // the examples of days 2 and 5 (the ones of day 9 are the quine
+        // and the large numbers above)
+        Case::new("day 2/example 1", &[1,9,10,3,2,3,11,0,99,30,40,50], &[], &[])
+            .with_image(&[3500,9,10,70,2,3,11,0,99,30,40,50]),
+        Case::new("day 2/example 2", &[1,0,0,0,99], &[], &[]).with_image(&[2,0,0,0,99]),
+        Case::new("day 2/example 3", &[2,3,0,3,99], &[], &[]).with_image(&[2,3,0,6,99]),
+        Case::new("day 2/example 4", &[2,4,4,5,99,0], &[], &[]).with_image(&[2,4,4,5,99,9801]),
+        Case::new("day 2/example 5", &[1,1,1,4,99,5,6,0,99], &[], &[]).with_image(&[30,1,1,4,2,5,6,0,99]),
+        Case::new("day 5/equal to 8, position, below", &[3,9,8,9,10,9,4,9,99,-1,8], &[7], &[0]),
+        Case::new("day 5/equal to 8, position, equal", &[3,9,8,9,10,9,4,9,99,-1,8], &[8], &[1]),
+        Case::new("day 5/equal to 8, position, above", &[3,9,8,9,10,9,4,9,99,-1,8], &[9], &[0]),
+        Case::new("day 5/less than 8, position, below", &[3,9,7,9,10,9,4,9,99,-1,8], &[7], &[1]),
+        Case::new("day 5/less than 8, position, equal", &[3,9,7,9,10,9,4,9,99,-1,8], &[8], &[0]),
+        Case::new("day 5/less than 8, position, above", &[3,9,7,9,10,9,4,9,99,-1,8], &[9], &[0]),
+        Case::new("day 5/equal to 8, immediate, below", &[3,3,1108,-1,8,3,4,3,99], &[7], &[0]),
+        Case::new("day 5/equal to 8, immediate, equal", &[3,3,1108,-1,8,3,4,3,99], &[8], &[1]),
+        Case::new("day 5/equal to 8, immediate, above", &[3,3,1108,-1,8,3,4,3,99], &[9], &[0]),
+        Case::new("day 5/less than 8, immediate, below", &[3,3,1107,-1,8,3,4,3,99], &[7], &[1]),
+        Case::new("day 5/less than 8, immediate, equal", &[3,3,1107,-1,8,3,4,3,99], &[8], &[0]),
+        Case::new("day 5/less than 8, immediate, above", &[3,3,1107,-1,8,3,4,3,99], &[9], &[0]),
+        Case::new("day 5/jump, position, zero", &[3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9], &[0], &[0]),
+        Case::new("day 5/jump, position, one", &[3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9], &[1], &[1]),
+        Case::new("day 5/jump, position, two", &[3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9], &[2], &[1]),
+        Case::new("day 5/jump, immediate, zero", &[3,3,1105,-1,9,1101,0,0,12,4,12,99,1], &[0], &[0]),
+        Case::new("day 5/jump, immediate, one", &[3,3,1105,-1,9,1101,0,0,12,4,12,99,1], &[1], &[1]),
+        Case::new("day 5/jump, immediate, two", &[3,3,1105,-1,9,1101,0,0,12,4,12,99,1], &[2], &[1]),
+        Case::new("day 5/compare to 8, below", &[3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &[5], &[999]),
+        Case::new("day 5/compare to 8, equal", &[3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &[8], &[1000]),
+        Case::new("day 5/compare to 8, above", &[3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99], &[15], &[1001]),
+
+        // halting
+        Case::new("halt/immediately", &[99], &[], &[]),
+    ]
+}
+
+// Runs all the cases, creating a machine for each program with `new`.
+// Returns the name of each failing case with the mismatch found.
+pub fn check_all<M: Machine>(new: impl Fn(&[i64]) -> M) -> Vec<(&'static str, String)> {
+    cases()
+        .into_iter()
+        .filter_map(|case| {
+            let mut machine = new(&case.program);
+            case.check(&mut machine).err().map(|e| (case.name, e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::IntCode;
+
+    #[test]
+    fn intcode() {
+        assert_eq!(check_all(IntCode::new), vec![]);
+    }
+}
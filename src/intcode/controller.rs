@@ -0,0 +1,85 @@
+// Controllers for robots driven by an Intcode program: the program
+// asks for an input whenever it needs one and reports what happens
+// through frames of output. The controller only reacts to these
+// events, so it can be tested without any program.
+use super::frame::{Frame, Frames};
+use super::{Machine, Status};
+
+pub trait Controller {
+    type Frame: Frame;
+
+    // The next input for the program, or None to stop driving it.
+    fn next_input(&mut self) -> Option<i64>;
+
+    fn on_output(&mut self, frame: Self::Frame);
+}
+
+// Runs a machine against a controller until the program halts (the
+// result is then `Halted`) or the controller stops providing input
+// (`WaitingInput`). An incomplete frame left at the end is dropped.
+pub fn drive<M: Machine, C: Controller>(machine: &mut M, controller: &mut C) -> Status {
+    let mut output = Vec::new();
+    loop {
+        machine.run();
+        output.extend(machine.take_output());
+        for frame in Frames::<C::Frame>::new(&mut output) {
+            controller.on_output(frame);
+        }
+        if machine.is_halted() {
+            return Status::Halted;
+        }
+        match controller.next_input() {
+            Some(v) => machine.push_input(v),
+            None => return Status::WaitingInput,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::IntCode;
+
+    // Sends 1, 2, 3, 4 and records the pairs received.
+    struct Counter {
+        next: i64,
+        received: Vec<[i64; 2]>,
+    }
+
+    impl Controller for Counter {
+        type Frame = [i64; 2];
+
+        fn next_input(&mut self) -> Option<i64> {
+            self.next += 1;
+            if self.next <= 4 {
+                Some(self.next)
+            } else {
+                None
+            }
+        }
+
+        fn on_output(&mut self, frame: [i64; 2]) {
+            self.received.push(frame);
+        }
+    }
+
+    #[test]
+    fn drive_until_stopped() {
+        // outputs an input, then its double when given another input
+        let program = [3, 20, 4, 20, 1002, 20, 2, 20, 3, 21, 4, 20, 1105, 1, 0];
+        let mut c = IntCode::new(&program);
+        let mut counter = Counter { next: 0, received: Vec::new() };
+        assert_eq!(drive(&mut c, &mut counter), Status::WaitingInput);
+        assert_eq!(counter.received, vec![[1, 2], [3, 6]]);
+        assert_eq!(c.output, vec![]);
+    }
+
+    #[test]
+    fn drive_until_halted() {
+        let mut c = IntCode::new(&[104, 1, 104, 2, 104, 3, 99]);
+        let mut counter = Counter { next: 0, received: Vec::new() };
+        assert_eq!(drive(&mut c, &mut counter), Status::Halted);
+        assert_eq!(counter.received, vec![[1, 2]]);
+        assert_eq!(counter.next, 0);
+    }
+}
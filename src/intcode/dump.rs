@@ -0,0 +1,213 @@
+// A memory viewer for `IntCode` machines: a paged dump of the memory,
+// with the cells in the printable ASCII range rendered as text, and a
+// few ways to search for sequences and strings.
+//
+//     -- 0..10 (ptr 9, base 3) --
+//      0:   1105     1     7 @  72  |  ...H
+//      4:    105    10     0   109  |  i..m
+//      8:      3 >  99              |  .c
+//
+// The cell at `ptr` is marked with `>`, the cell at `base` with `@`
+// (and `*` when they are the same).
+use super::IntCode;
+use std::fmt::Display;
+use std::ops::Range;
+
+pub struct Dump<'a> {
+    memory: &'a [i64],
+    ptr: Option<usize>,
+    base: Option<i64>,
+    range: Range<usize>,
+    width: usize, // cells per line
+}
+
+impl<'a> Dump<'a> {
+    // Dumps the memory of a machine, marking its `ptr` and `base`.
+    pub fn new(machine: &'a IntCode) -> Dump<'a> {
+        Dump {
+            ptr: Some(machine.ptr()),
+            base: Some(machine.base()),
+            ..Dump::of(&machine.memory)
+        }
+    }
+
+    // Dumps any memory.
+    pub fn of(memory: &'a [i64]) -> Dump<'a> {
+        Dump {
+            memory,
+            ptr: None,
+            base: None,
+            range: 0..memory.len(),
+            width: 8,
+        }
+    }
+
+    pub fn range(mut self, range: Range<usize>) -> Dump<'a> {
+        // an empty range at the end if it starts past it
+        let end = range.end.min(self.memory.len());
+        self.range = range.start.min(end)..end;
+        self
+    }
+
+    pub fn width(mut self, width: usize) -> Dump<'a> {
+        assert!(width > 0, "empty lines");
+        self.width = width;
+        self
+    }
+
+    // Splits the dump in pages of `lines` lines each.
+    pub fn pages(&self, lines: usize) -> Vec<Dump<'a>> {
+        assert!(lines > 0, "empty pages");
+        let size = lines * self.width;
+        (self.range.start..self.range.end)
+            .step_by(size)
+            .map(|start| Dump {
+                memory: self.memory,
+                ptr: self.ptr,
+                base: self.base,
+                range: start..(start + size).min(self.range.end),
+                width: self.width,
+            })
+            .collect()
+    }
+
+    fn marker(&self, addr: usize) -> char {
+        let at_base = self.base == Some(addr as i64);
+        match (self.ptr == Some(addr), at_base) {
+            (true, true) => '*',
+            (true, false) => '>',
+            (false, true) => '@',
+            (false, false) => ' ',
+        }
+    }
+}
+
+impl<'a> Display for Dump<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let cells = &self.memory[self.range.clone()];
+        let digits = cells.iter().map(|v| v.to_string().len()).max().unwrap_or(1);
+        let addr_digits = self.range.end.max(1).to_string().len();
+
+        write!(f, "-- {}..{}", self.range.start, self.range.end)?;
+        match (self.ptr, self.base) {
+            (Some(ptr), Some(base)) => writeln!(f, " (ptr {}, base {}) --", ptr, base)?,
+            _ => writeln!(f, " --")?,
+        }
+        for (line, chunk) in cells.chunks(self.width).enumerate() {
+            let start = self.range.start + line * self.width;
+            write!(f, "{:>w$}: ", start, w = addr_digits)?;
+            for (i, v) in chunk.iter().enumerate() {
+                write!(f, " {}{:>w$}", self.marker(start + i), v, w = digits)?;
+            }
+            for _ in chunk.len()..self.width {
+                write!(f, " {:>w$}", "", w = digits + 1)?;
+            }
+            let text: String = chunk.iter().map(|v| ascii(*v).unwrap_or('.')).collect();
+            writeln!(f, "  |  {}", text)?;
+        }
+        Ok(())
+    }
+}
+
+// the character of a cell in the printable ASCII range
+fn ascii(v: i64) -> Option<char> {
+    if (32..127).contains(&v) {
+        Some(v as u8 as char)
+    } else {
+        None
+    }
+}
+
+// Finds all the addresses where a sequence of values starts.
+pub fn find_sequence(memory: &[i64], sequence: &[i64]) -> Vec<usize> {
+    if sequence.is_empty() {
+        return Vec::new();
+    }
+    memory
+        .windows(sequence.len())
+        .enumerate()
+        .filter(|(_, w)| *w == sequence)
+        .map(|(addr, _)| addr)
+        .collect()
+}
+
+// Finds all the addresses where a string is stored, one character per
+// cell.
+pub fn find_string(memory: &[i64], s: &str) -> Vec<usize> {
+    let sequence: Vec<i64> = s.bytes().map(|b| b as i64).collect();
+    find_sequence(memory, &sequence)
+}
+
+// Finds the runs of at least `min_len` printable characters (or line
+// feeds), with their addresses.
+pub fn strings(memory: &[i64], min_len: usize) -> Vec<(usize, String)> {
+    let mut found = Vec::new();
+    let mut start = 0;
+    let mut current = String::new();
+    for (addr, v) in memory.iter().chain(std::iter::once(&0)).enumerate() {
+        match ascii(*v).or(if *v == 10 { Some('\n') } else { None }) {
+            Some(c) => {
+                if current.is_empty() {
+                    start = addr;
+                }
+                current.push(c);
+            }
+            None => {
+                if current.len() >= min_len.max(1) {
+                    found.push((start, current.clone()));
+                }
+                current.clear();
+            }
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        // jump over the text "Hi\n", then halt
+        let mut c = IntCode::new(&[1105, 1, 7, 72, 105, 10, 0, 109, 3, 99]);
+        c.step();
+        c.step();
+        assert_eq!(
+            Dump::new(&c).width(4).to_string(),
+            [
+                "-- 0..10 (ptr 9, base 3) --",
+                " 0:   1105     1     7 @  72  |  ...H",
+                " 4:    105    10     0   109  |  i..m",
+                " 8:      3 >  99              |  .c",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn pages() {
+        let memory: Vec<i64> = (0..20).collect();
+        let pages = Dump::of(&memory).range(2..15).width(3).pages(2);
+        let ranges: Vec<_> = pages.iter().map(|p| p.range.clone()).collect();
+        assert_eq!(ranges, vec![2..8, 8..14, 14..15]);
+        assert_eq!(pages[2].to_string(), "-- 14..15 --\n14:   14          |  .\n");
+
+        // ranges past the end, or backwards, are empty
+        assert_eq!(Dump::of(&memory).range(30..40).to_string(), "-- 20..20 --\n");
+        let (start, end) = (12, 5);
+        assert_eq!(Dump::of(&memory).range(start..end).to_string(), "-- 5..5 --\n");
+        assert_eq!(Dump::of(&memory).range(start..end).pages(1).len(), 0);
+    }
+
+    #[test]
+    fn search() {
+        let memory = [1, 2, 3, 72, 105, 1, 2, 72, 105, 33, 10, 0, 1, 2];
+        assert_eq!(find_sequence(&memory, &[1, 2]), vec![0, 5, 12]);
+        assert_eq!(find_sequence(&memory, &[]), vec![]);
+        assert_eq!(find_string(&memory, "Hi"), vec![3, 7]);
+        assert_eq!(find_string(&memory, "Hi!"), vec![7]);
+        assert_eq!(strings(&memory, 2), vec![(3, "Hi".to_string()), (7, "Hi!\n".to_string())]);
+    }
+}
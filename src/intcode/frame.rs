@@ -0,0 +1,103 @@
+// Framing of the output of a machine into messages made of a fixed
+// number of values. Incomplete frames stay in the output buffer until
+// the following runs complete them.
+use std::convert::TryInto;
+use std::marker::PhantomData;
+
+// A message made of `LEN` consecutive output values.
+pub trait Frame: Sized {
+    const LEN: usize;
+    fn decode(values: &[i64]) -> Self;
+}
+
+impl<const N: usize> Frame for [i64; N] {
+    const LEN: usize = N;
+
+    fn decode(values: &[i64]) -> Self {
+        values.try_into().unwrap()
+    }
+}
+
+// Iterator over the complete frames in an output buffer. The frames
+// yielded are removed from the buffer when the iterator is dropped.
+pub struct Frames<'a, F> {
+    output: &'a mut Vec<i64>,
+    pos: usize,
+    frame: PhantomData<F>,
+}
+
+impl<'a, F: Frame> Frames<'a, F> {
+    pub fn new(output: &'a mut Vec<i64>) -> Frames<'a, F> {
+        assert!(F::LEN > 0, "empty frames");
+        Frames {
+            output,
+            pos: 0,
+            frame: PhantomData,
+        }
+    }
+}
+
+impl<'a, F: Frame> Iterator for Frames<'a, F> {
+    type Item = F;
+
+    fn next(&mut self) -> Option<F> {
+        if self.pos + F::LEN > self.output.len() {
+            return None;
+        }
+        let f = F::decode(&self.output[self.pos..self.pos + F::LEN]);
+        self.pos += F::LEN;
+        Some(f)
+    }
+}
+
+impl<'a, F> Drop for Frames<'a, F> {
+    fn drop(&mut self) {
+        self.output.drain(..self.pos);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::IntCode;
+
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: i64,
+        y: i64,
+    }
+
+    impl Frame for Point {
+        const LEN: usize = 2;
+
+        fn decode(values: &[i64]) -> Self {
+            Point { x: values[0], y: values[1] }
+        }
+    }
+
+    #[test]
+    fn partial_frames() {
+        // outputs 1, 2, 3, then waits for input and outputs it
+        let mut c = IntCode::new(&[104,1,104,2,104,3,3,100,4,100,99]);
+        c.run();
+        let v: Vec<Point> = c.frames().collect();
+        assert_eq!(v, vec![Point { x: 1, y: 2 }]);
+        assert_eq!(c.take_exact(2), None);
+        assert_eq!(c.output, vec![3]);
+
+        c.input.push_back(4);
+        c.run();
+        assert_eq!(c.next_frame::<Point>(), Some(Point { x: 3, y: 4 }));
+        assert_eq!(c.next_frame::<[i64; 1]>(), None);
+    }
+
+    #[test]
+    fn early_stop() {
+        let mut c = IntCode::new(&[104,1,104,2,104,3,104,4,104,5,99]);
+        c.run();
+        let first = c.frames::<[i64; 2]>().next();
+        assert_eq!(first, Some([1, 2]));
+        assert_eq!(c.take_exact(2), Some(vec![3, 4]));
+        assert_eq!(c.output, vec![5]);
+    }
+}
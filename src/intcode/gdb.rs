@@ -0,0 +1,418 @@
+// A stub for the GDB remote serial protocol, so that a standard
+// debugger front-end can attach to an `IntCode` machine, e.g. with
+// `target remote localhost:1234`.
+//
+// The memory is exposed as the address space, with each cell taking
+// 8 bytes (little-endian), so cell `n` is at address `8 * n`. The
+// registers are `ptr` (0) and `base` (1), as 64-bit values. Stepping,
+// continuing and software breakpoints are supported. When the machine
+// blocks on input the stub reports SIGSTOP, and when it halts it
+// reports an exit. An instruction that can't be executed (an invalid
+// opcode or mode, an address out of range, an overflow) is not run:
+// the stub reports SIGILL, leaving the machine as it was.
+use super::{IntCode, Status};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::net::TcpListener;
+
+const CELL: usize = 8;
+const MAX_TRANSFER: usize = 0x800; // bytes read or written at once, within the packet size
+const MAX_ADDRESS: usize = 1 << 24; // cells the machine may access, so that it can't allocate without limit
+
+pub struct GdbStub<'a, S> {
+    machine: &'a mut IntCode,
+    stream: S,
+    breakpoints: HashSet<usize>,
+}
+
+impl<'a, S: Read + Write> GdbStub<'a, S> {
+    pub fn new(machine: &'a mut IntCode, stream: S) -> GdbStub<'a, S> {
+        GdbStub {
+            machine,
+            stream,
+            breakpoints: HashSet::new(),
+        }
+    }
+
+    // Serves requests until the debugger detaches, kills the machine
+    // or closes the connection.
+    pub fn serve(&mut self) -> io::Result<()> {
+        while let Some(packet) = self.read_packet()? {
+            match packet.as_bytes().first() {
+                Some(b'D') => {
+                    self.write_packet("OK")?;
+                    return Ok(());
+                }
+                Some(b'k') => return Ok(()),
+                _ => {
+                    let reply = self.handle(&packet);
+                    self.write_packet(&reply)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, packet: &str) -> String {
+        if packet.is_empty() || !packet.is_ascii() {
+            return String::new();
+        }
+        let (cmd, args) = packet.split_at(1);
+        match cmd {
+            "?" => self.stop_reply(Status::Running),
+            "g" => format!("{}{}", hex64(self.machine.ptr() as i64), hex64(self.machine.base())),
+            "G" if args.len() == 32 => match (unhex64(&args[..16]), unhex64(&args[16..])) {
+                (Some(ptr), Some(base)) if self.is_code(ptr) => {
+                    self.machine.set_ptr(ptr as usize);
+                    self.machine.set_base(base);
+                    "OK".to_string()
+                }
+                _ => "E01".to_string(),
+            },
+            "p" => match usize::from_str_radix(args, 16) {
+                Ok(0) => hex64(self.machine.ptr() as i64),
+                Ok(1) => hex64(self.machine.base()),
+                _ => "E01".to_string(),
+            },
+            "P" => {
+                let mut it = args.splitn(2, '=');
+                let reg = it.next().and_then(|r| usize::from_str_radix(r, 16).ok());
+                match (reg, it.next().and_then(unhex64)) {
+                    (Some(0), Some(v)) if self.is_code(v) => self.machine.set_ptr(v as usize),
+                    (Some(1), Some(v)) => self.machine.set_base(v),
+                    _ => return "E01".to_string(),
+                }
+                "OK".to_string()
+            }
+            "m" => match parse_range(args) {
+                Some((addr, len)) => (addr..addr + len)
+                    .map(|a| format!("{:02x}", self.read_byte(a)))
+                    .collect(),
+                None => "E01".to_string(),
+            },
+            "M" => {
+                let mut it = args.splitn(2, ':');
+                match (it.next().and_then(parse_range), it.next()) {
+                    (Some((addr, len)), Some(data)) if data.len() == 2 * len && addr + len <= self.size() => {
+                        for i in 0..len {
+                            match u8::from_str_radix(&data[2 * i..2 * i + 2], 16) {
+                                Ok(b) => self.write_byte(addr + i, b),
+                                Err(_) => return "E01".to_string(),
+                            }
+                        }
+                        "OK".to_string()
+                    }
+                    _ => "E01".to_string(),
+                }
+            }
+            "s" => match self.can_step() {
+                true => {
+                    let status = self.machine.step();
+                    self.stop_reply(status)
+                }
+                false => "S04".to_string(),
+            },
+            "c" => match self.resume() {
+                Some(status) => self.stop_reply(status),
+                None => "S04".to_string(),
+            },
+            "Z" | "z" => {
+                let mut it = args.split(',');
+                let kind = it.next();
+                let addr = it.next().and_then(|a| usize::from_str_radix(a, 16).ok());
+                match (kind, addr) {
+                    (Some("0"), Some(addr)) => {
+                        if cmd == "Z" {
+                            self.breakpoints.insert(addr / CELL);
+                        } else {
+                            self.breakpoints.remove(&(addr / CELL));
+                        }
+                        "OK".to_string()
+                    }
+                    _ => String::new(),
+                }
+            }
+            "H" => "OK".to_string(),
+            "q" if args.starts_with("Supported") => "PacketSize=1000;swbreak+".to_string(),
+            "q" if args == "Attached" => "1".to_string(),
+            _ => String::new(), // not supported
+        }
+    }
+
+    // Runs until a breakpoint is reached, or the machine stops. Returns
+    // None on an instruction that can't be executed.
+    fn resume(&mut self) -> Option<Status> {
+        loop {
+            if !self.can_step() {
+                return None;
+            }
+            let status = self.machine.step();
+            if status != Status::Running || self.breakpoints.contains(&self.machine.ptr()) {
+                return Some(status);
+            }
+        }
+    }
+
+    // Whether the instruction at `ptr` can be executed without making
+    // the machine panic: a valid opcode and modes, parameters within the
+    // memory, addresses within MAX_ADDRESS, no overflow, and the next
+    // instruction within the memory.
+    fn can_step(&self) -> bool {
+        let (memory, ptr, base) = (&self.machine.memory, self.machine.ptr(), self.machine.base());
+        let instr = memory[ptr];
+        let len = match instr % 100 {
+            1 | 2 | 7 | 8 => 4,
+            3 | 4 | 9 => 2,
+            5 | 6 => 3,
+            99 => 1,
+            _ => return false,
+        };
+        if instr < 0 || ptr + len > memory.len() {
+            return false;
+        }
+
+        let mut addr = [0; 3];
+        let mut modes = instr / 100;
+        for (p, a) in addr.iter_mut().enumerate().take(len - 1) {
+            let param = memory[ptr + 1 + p];
+            let target = match modes % 10 {
+                0 => Some(param),
+                1 => Some((ptr + 1 + p) as i64),
+                2 => base.checked_add(param),
+                _ => None,
+            };
+            match target.and_then(|t| usize::try_from(t).ok()).filter(|t| *t < MAX_ADDRESS) {
+                Some(t) => *a = t,
+                None => return false,
+            }
+            modes /= 10;
+        }
+
+        // the memory grows to hold the addresses accessed
+        let size = addr.iter().take(len - 1).map(|a| a + 1).fold(memory.len(), usize::max);
+        let value = |a: usize| memory.get(a).copied().unwrap_or(0);
+        let jump = |taken: bool| if taken { usize::try_from(value(addr[1])).ok() } else { Some(ptr + len) };
+        let next = match instr % 100 {
+            1 => value(addr[0]).checked_add(value(addr[1])).map(|_| ptr + len),
+            2 => value(addr[0]).checked_mul(value(addr[1])).map(|_| ptr + len),
+            5 => jump(value(addr[0]) != 0),
+            6 => jump(value(addr[0]) == 0),
+            9 => base.checked_add(value(addr[0])).map(|_| ptr + len),
+            99 => Some(ptr),
+            _ => Some(ptr + len),
+        };
+        next.is_some_and(|n| n < size)
+    }
+
+    // Whether `ptr` is a valid instruction pointer: the machine can only
+    // execute cells within its memory.
+    fn is_code(&self, ptr: i64) -> bool {
+        0 <= ptr && (ptr as usize) < self.machine.memory.len()
+    }
+
+    // the size of the memory in bytes: writes beyond it are refused, so
+    // that the debugger cannot make the machine allocate without limit
+    fn size(&self) -> usize {
+        self.machine.memory.len() * CELL
+    }
+
+    fn stop_reply(&self, status: Status) -> String {
+        match status {
+            _ if self.machine.is_halted() => "W00".to_string(),
+            Status::WaitingInput => "S13".to_string(),
+            _ => "S05".to_string(),
+        }
+    }
+
+    fn read_byte(&self, addr: usize) -> u8 {
+        let cell = self.machine.memory.get(addr / CELL).copied().unwrap_or(0);
+        cell.to_le_bytes()[addr % CELL]
+    }
+
+    fn write_byte(&mut self, addr: usize, b: u8) {
+        let cell = self.machine.memory.get(addr / CELL).copied().unwrap_or(0);
+        let mut bytes = cell.to_le_bytes();
+        bytes[addr % CELL] = b;
+        self.machine.poke(addr / CELL, i64::from_le_bytes(bytes));
+    }
+
+    // Reads the next packet, acknowledging it. Returns None at the end
+    // of the stream.
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            // skip acknowledgements and interrupts until the start of a packet
+            loop {
+                match self.read_u8()? {
+                    None => return Ok(None),
+                    Some(b'$') => break,
+                    Some(_) => {}
+                }
+            }
+            let mut data = Vec::new();
+            loop {
+                match self.read_u8()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(c) => data.push(c),
+                }
+            }
+            let mut checksum = [0; 2];
+            self.stream.read_exact(&mut checksum)?;
+            let expected = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|c| u8::from_str_radix(c, 16).ok());
+            if expected == Some(checksum_of(&data)) {
+                self.stream.write_all(b"+")?;
+                return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+            }
+            self.stream.write_all(b"-")?;
+        }
+    }
+
+    fn write_packet(&mut self, data: &str) -> io::Result<()> {
+        write!(self.stream, "${}#{:02x}", data, checksum_of(data.as_bytes()))?;
+        self.stream.flush()
+    }
+
+    fn read_u8(&mut self) -> io::Result<Option<u8>> {
+        let mut b = [0];
+        match self.stream.read(&mut b)? {
+            0 => Ok(None),
+            _ => Ok(Some(b[0])),
+        }
+    }
+}
+
+// Waits for a debugger to connect on `addr` (e.g. "127.0.0.1:1234"),
+// then serves it.
+pub fn listen(machine: &mut IntCode, addr: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(addr)?;
+    let (stream, _) = listener.accept()?;
+    stream.set_nodelay(true)?;
+    GdbStub::new(machine, stream).serve()
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0, |s, b| s.wrapping_add(*b))
+}
+
+// registers are sent in target byte order (little-endian)
+fn hex64(v: i64) -> String {
+    v.to_le_bytes().iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unhex64(s: &str) -> Option<i64> {
+    if s.len() != 16 {
+        return None;
+    }
+    let mut bytes = [0; 8];
+    for (i, b) in bytes.iter_mut().enumerate() {
+        *b = u8::from_str_radix(s.get(2 * i..2 * i + 2)?, 16).ok()?;
+    }
+    Some(i64::from_le_bytes(bytes))
+}
+
+// parses "addr,length", for at most MAX_TRANSFER bytes
+fn parse_range(s: &str) -> Option<(usize, usize)> {
+    let mut it = s.split(',');
+    let addr = usize::from_str_radix(it.next()?, 16).ok()?;
+    let len = usize::from_str_radix(it.next()?, 16).ok()?;
+    addr.checked_add(len).filter(|_| len <= MAX_TRANSFER)?;
+    Some((addr, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpStream;
+    use std::thread;
+
+    // A minimal debugger front-end.
+    struct Client(TcpStream);
+
+    impl Client {
+        fn command(&mut self, cmd: &str) -> String {
+            write!(self.0, "${}#{:02x}", cmd, checksum_of(cmd.as_bytes())).unwrap();
+            let mut reply = Vec::new();
+            let mut b = [0];
+            self.0.read_exact(&mut b).unwrap();
+            assert_eq!(&b, b"+");
+            while b[0] != b'$' {
+                self.0.read_exact(&mut b).unwrap();
+            }
+            loop {
+                self.0.read_exact(&mut b).unwrap();
+                if b[0] == b'#' {
+                    break;
+                }
+                reply.push(b[0]);
+            }
+            let mut checksum = [0; 2];
+            self.0.read_exact(&mut checksum).unwrap();
+            self.0.write_all(b"+").unwrap();
+            String::from_utf8(reply).unwrap()
+        }
+    }
+
+    #[test]
+    fn session() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            // output 7, read a value, output it doubled
+            let mut c = IntCode::new(&[104,7,3,11,1002,11,2,11,4,11,99,0]);
+            let (stream, _) = listener.accept().unwrap();
+            stream.set_nodelay(true).unwrap();
+            GdbStub::new(&mut c, stream).serve().unwrap();
+            c.output
+        });
+
+        let stream = TcpStream::connect(addr).unwrap();
+        stream.set_nodelay(true).unwrap();
+        let mut gdb = Client(stream);
+        assert_eq!(gdb.command("qSupported:swbreak+"), "PacketSize=1000;swbreak+");
+        assert_eq!(gdb.command("?"), "S05");
+        assert_eq!(gdb.command("g"), format!("{}{}", hex64(0), hex64(0)));
+
+        // step over the first output
+        assert_eq!(gdb.command("s"), "S05");
+        assert_eq!(gdb.command("p0"), hex64(2));
+
+        // continue until blocked on input, then provide it through memory
+        assert_eq!(gdb.command("c"), "S13");
+        assert_eq!(gdb.command("m10,8"), hex64(3));
+        assert_eq!(gdb.command(&format!("M58,8:{}", hex64(21))), "OK");
+        assert_eq!(gdb.command("P0=0400000000000000"), "OK");
+
+        // stop at a breakpoint on the output instruction
+        assert_eq!(gdb.command("Z0,40,1"), "OK");
+        assert_eq!(gdb.command("c"), "S05");
+        assert_eq!(gdb.command("p0"), hex64(8));
+        assert_eq!(gdb.command("m58,8"), hex64(42));
+        assert_eq!(gdb.command("z0,40,1"), "OK");
+
+        // out of range requests
+        assert_eq!(gdb.command("P0=0c00000000000000"), "E01");
+        assert_eq!(gdb.command(&format!("G{}{}", hex64(1 << 40), hex64(0))), "E01");
+        assert_eq!(gdb.command("mfffffffffffffff8,10"), "E01");
+        assert_eq!(gdb.command("m0,100000"), "E01");
+        assert_eq!(gdb.command(&format!("M60,8:{}", hex64(1))), "E01");
+        assert_eq!(gdb.command("p0"), hex64(8));
+
+        // instructions that can't be executed are not run
+        assert_eq!(gdb.command("P0=0b00000000000000"), "OK"); // the data cell
+        assert_eq!(gdb.command("s"), "S04");
+        assert_eq!(gdb.command("c"), "S04");
+        assert_eq!(gdb.command("p0"), hex64(11));
+        assert_eq!(gdb.command(&format!("M0,10:{}{}", hex64(204), hex64(-5))), "OK"); // output mem[base - 5]
+        assert_eq!(gdb.command("P0=0000000000000000"), "OK");
+        assert_eq!(gdb.command("s"), "S04");
+        assert_eq!(gdb.command(&format!("M0,10:{}{}", hex64(104), hex64(7))), "OK");
+        assert_eq!(gdb.command("P0=0800000000000000"), "OK");
+        assert_eq!(gdb.command("c"), "W00");
+        assert_eq!(gdb.command("D"), "OK");
+
+        assert_eq!(server.join().unwrap(), vec![7, 42]);
+    }
+}
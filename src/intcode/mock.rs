@@ -0,0 +1,185 @@
+// Fake guests implementing `Machine`, to test the code driving Intcode
+// programs against known worlds instead of the real puzzle inputs.
+// `Mock` answers each input value with a closure, `Text` answers each
+// line of ASCII input, and `scripted` follows a fixed script.
+//
+// The guests have no memory: `read` always returns zero.
+use super::Machine;
+use std::collections::{HashMap, VecDeque};
+
+#[derive(Clone)]
+pub struct Mock<F> {
+    respond: F, // the output for an input, or None to halt
+    input: VecDeque<i64>,
+    output: Vec<i64>,
+    halted: bool,
+}
+
+impl<F: FnMut(i64) -> Option<Vec<i64>>> Mock<F> {
+    pub fn new(respond: F) -> Mock<F> {
+        Mock {
+            respond,
+            input: VecDeque::new(),
+            output: Vec::new(),
+            halted: false,
+        }
+    }
+
+    // Output produced before reading any input.
+    pub fn with_output(mut self, output: &[i64]) -> Self {
+        self.output.extend_from_slice(output);
+        self
+    }
+}
+
+impl<F: FnMut(i64) -> Option<Vec<i64>>> Machine for Mock<F> {
+    fn push_input(&mut self, value: i64) {
+        self.input.push_back(value);
+    }
+
+    fn run(&mut self) {
+        while !self.halted {
+            let value = match self.input.pop_front() {
+                Some(v) => v,
+                None => return,
+            };
+            match (self.respond)(value) {
+                Some(output) => self.output.extend(output),
+                None => self.halted = true,
+            }
+        }
+    }
+
+    fn take_output(&mut self) -> Vec<i64> {
+        std::mem::take(&mut self.output)
+    }
+
+    fn read(&self, _addr: usize) -> i64 {
+        0
+    }
+
+    fn is_halted(&self) -> bool {
+        self.halted
+    }
+}
+
+#[derive(Clone)]
+pub struct Text<F> {
+    respond: F, // the text for a line (without the line feed), or None to halt
+    line: String,
+    input: VecDeque<i64>,
+    output: Vec<i64>,
+    halted: bool,
+}
+
+impl<F: FnMut(&str) -> Option<String>> Text<F> {
+    // A guest printing `intro`, then answering each line of input.
+    pub fn new(intro: &str, respond: F) -> Text<F> {
+        Text {
+            respond,
+            line: String::new(),
+            input: VecDeque::new(),
+            output: intro.bytes().map(|b| b as i64).collect(),
+            halted: false,
+        }
+    }
+}
+
+// A text guest answering the commands found in `script`, and halting on
+// any other command.
+pub fn scripted(intro: &str, script: &[(&str, &str)]) -> Text<impl FnMut(&str) -> Option<String> + Clone> {
+    let script: HashMap<String, String> = script.iter().map(|(c, r)| (c.to_string(), r.to_string())).collect();
+    Text::new(intro, move |command: &str| script.get(command).cloned())
+}
+
+impl<F: FnMut(&str) -> Option<String>> Machine for Text<F> {
+    fn push_input(&mut self, value: i64) {
+        self.input.push_back(value);
+    }
+
+    fn run(&mut self) {
+        while !self.halted {
+            match self.input.pop_front() {
+                Some(10) => {
+                    match (self.respond)(&self.line) {
+                        Some(text) => self.output.extend(text.bytes().map(|b| b as i64)),
+                        None => self.halted = true,
+                    }
+                    self.line.clear();
+                }
+                Some(c) => self.line.push(c as u8 as char),
+                None => return,
+            }
+        }
+    }
+
+    fn take_output(&mut self) -> Vec<i64> {
+        std::mem::take(&mut self.output)
+    }
+
+    fn read(&self, _addr: usize) -> i64 {
+        0
+    }
+
+    fn is_halted(&self) -> bool {
+        self.halted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::controller::{drive, Controller};
+    use crate::intcode::Status;
+
+    // Asks for the squares of 1, 2, 3.
+    struct Squares(Vec<i64>);
+
+    impl Controller for Squares {
+        type Frame = [i64; 1];
+
+        fn next_input(&mut self) -> Option<i64> {
+            if self.0.len() < 3 { Some(self.0.len() as i64 + 1) } else { None }
+        }
+
+        fn on_output(&mut self, [v]: [i64; 1]) {
+            self.0.push(v);
+        }
+    }
+
+    #[test]
+    fn closure() {
+        let mut squares = Squares(Vec::new());
+        let mut guest = Mock::new(|x| Some(vec![x * x]));
+        assert_eq!(drive(&mut guest, &mut squares), Status::WaitingInput);
+        assert_eq!(squares.0, vec![1, 4, 9]);
+
+        // halts on the first negative input
+        let mut guest = Mock::new(|x| if x < 0 { None } else { Some(vec![x]) }).with_output(&[7]);
+        for v in &[1, -1, 2] {
+            guest.push_input(*v);
+        }
+        guest.run();
+        assert!(guest.is_halted());
+        assert_eq!(guest.take_output(), vec![7, 1]);
+    }
+
+    #[test]
+    fn text() {
+        let mut guest = Text::new("Name?\n", |name| match name {
+            "" => None,
+            _ => Some(format!("Hello, {}!\n", name)),
+        });
+        assert_eq!(guest.run_ascii_command(""), ("Name?\n".to_string(), vec![]));
+        assert_eq!(guest.run_ascii_command("Ada").0, "Hello, Ada!\n");
+        guest.push_input(10);
+        guest.run();
+        assert!(guest.is_halted());
+
+        let mut guest = scripted("> ", &[("look", "A room.\n> ")]);
+        assert_eq!(guest.run_ascii_command("").0, "> ");
+        assert_eq!(guest.run_ascii_command("look").0, "A room.\n> ");
+        assert_eq!(guest.run_ascii_command("jump").0, "");
+        assert!(guest.is_halted());
+    }
+}
@@ -0,0 +1,110 @@
+// Events reported by an `IntCode` machine to its observer, and a few
+// observers to route them to stdout, to a writer or to memory.
+use std::fmt::Display;
+use std::io::Write;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    CommandSent(String),  // an ASCII command loaded as input
+    TextReceived(String), // ASCII text produced by the program
+    ValueReceived(i64),   // a non-ASCII value produced by an ASCII program
+    Halted,
+}
+
+pub trait Observer {
+    fn notify(&mut self, event: &Event);
+}
+
+// Prints the conversation with the machine on stdout.
+pub struct Stdout;
+
+impl Observer for Stdout {
+    fn notify(&mut self, event: &Event) {
+        match event {
+            Event::CommandSent(s) => println!("{}", s),
+            Event::TextReceived(s) => print!("{}", s),
+            Event::ValueReceived(v) => println!("{}", v),
+            Event::Halted => {}
+        }
+    }
+}
+
+// Writes the conversation with the machine to a file (or any writer).
+pub struct Writer<W: Write>(pub W);
+
+impl<W: Write> Observer for Writer<W> {
+    fn notify(&mut self, event: &Event) {
+        let _ = match event {
+            Event::CommandSent(s) => writeln!(self.0, "{}", s),
+            Event::TextReceived(s) => write!(self.0, "{}", s),
+            Event::ValueReceived(v) => writeln!(self.0, "{}", v),
+            Event::Halted => Ok(()),
+        };
+    }
+}
+
+// Records all the events in memory, to be inspected or printed later.
+#[derive(Default)]
+pub struct Transcript(pub Vec<Event>);
+
+impl Observer for Transcript {
+    fn notify(&mut self, event: &Event) {
+        self.0.push(event.clone());
+    }
+}
+
+impl Display for Transcript {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for event in self.0.iter() {
+            match event {
+                Event::CommandSent(s) => writeln!(f, "{}", s)?,
+                Event::TextReceived(s) => write!(f, "{}", s)?,
+                Event::ValueReceived(v) => writeln!(f, "{}", v)?,
+                Event::Halted => writeln!(f, "<halted>")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::{IntCode, Machine};
+
+    #[test]
+    fn transcript() {
+        // echo one character, then print "hi" and 1000
+        let mut c = IntCode::new(&[3,100,4,100,104,104,104,105,104,10,104,1000,99]);
+        let t = c.observe(Transcript::default());
+        c.run_ascii_command("x");
+        c.run_ascii_command("");
+        c.run();
+        assert_eq!(
+            t.borrow().0,
+            vec![
+                Event::CommandSent("x".to_string()),
+                Event::TextReceived("xhi\n".to_string()),
+                Event::ValueReceived(1000),
+                Event::Halted,
+            ]
+        );
+
+        // the same events when driven without ASCII commands
+        let mut c = IntCode::new(&[3,100,4,100,104,104,104,105,104,10,104,1000,99]);
+        let t = c.observe(Transcript::default());
+        c.push_input(b'x' as i64);
+        c.step();
+        c.step();
+        c.run();
+        assert_eq!(
+            t.borrow().0,
+            vec![
+                Event::TextReceived("x".to_string()),
+                Event::TextReceived("hi\n".to_string()),
+                Event::ValueReceived(1000),
+                Event::Halted,
+            ]
+        );
+    }
+}
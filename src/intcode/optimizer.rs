@@ -0,0 +1,373 @@
+// A peephole optimizer for Intcode programs.
+//
+// Programs can compute jump targets and return addresses, so the
+// rewrites never move code: every instruction keeps its address and
+// length. The only rewrites that save steps are kept: runs of
+// instructions with no effect (jumps never taken or to the next
+// instruction, and `add 0`/`mul 1` moves of a cell onto itself) are
+// jumped over, and jumps that land on other jumps are threaded.
+// Instructions that the program reads or writes as data are never
+// touched.
+//
+// The cells accessed in relative mode, and the code reached by computed
+// jumps, can't be known without running the program, so programs using
+// them are left unchanged. That rules out the programs with functions
+// (from day 9 on), which keep their stack in relative mode.
+use super::{IntCode, Machine};
+use std::collections::{BTreeMap, HashSet};
+
+#[derive(Clone, Copy, Debug)]
+struct Instr {
+    addr: usize,
+    op: i64,
+    len: usize,
+    modes: [i64; 3],
+    params: [i64; 3],
+}
+
+impl Instr {
+    fn decode(program: &[i64], addr: usize) -> Option<Instr> {
+        let mut instr = *program.get(addr)?;
+        let op = instr % 100;
+        let len = match op {
+            1 | 2 | 7 | 8 => 4,
+            3 | 4 | 9 => 2,
+            5 | 6 => 3,
+            99 => 1,
+            _ => return None,
+        };
+        instr /= 100;
+        let mut modes = [0; 3];
+        let mut params = [0; 3];
+        for p in 0..len - 1 {
+            modes[p] = instr % 10;
+            params[p] = *program.get(addr + 1 + p)?;
+            instr /= 10;
+            if modes[p] > 2 || (modes[p] == 1 && writes(op) == Some(p)) {
+                return None;
+            }
+        }
+        Some(Instr { addr, op, len, modes, params })
+    }
+
+    fn encode(&self, program: &mut [i64]) {
+        program[self.addr] = self.op + 100 * self.modes[0] + 1000 * self.modes[1] + 10000 * self.modes[2];
+        program[self.addr + 1..self.addr + self.len].copy_from_slice(&self.params[..self.len - 1]);
+    }
+
+    fn next(&self) -> usize {
+        self.addr + self.len
+    }
+
+    fn cells(&self) -> std::ops::Range<usize> {
+        self.addr..self.next()
+    }
+
+    // the immediate target of a jump
+    fn target(&self) -> Option<usize> {
+        match self.op {
+            5 | 6 if self.modes[1] == 1 && self.params[1] >= 0 => Some(self.params[1] as usize),
+            _ => None,
+        }
+    }
+
+    // whether a jump is always (Some(true)) or never (Some(false)) taken
+    fn taken(&self) -> Option<bool> {
+        match (self.op, self.modes[0]) {
+            (5, 1) => Some(self.params[0] != 0),
+            (6, 1) => Some(self.params[0] == 0),
+            _ => None,
+        }
+    }
+
+    // true for instructions with no effect at all
+    fn is_dead(&self) -> bool {
+        match self.op {
+            5 | 6 => self.taken() == Some(false) || self.target() == Some(self.next()),
+            1 | 2 => self.source() == Some((self.modes[2], self.params[2])),
+            _ => false,
+        }
+    }
+
+    // the operand copied by an `add 0`/`mul 1` move
+    fn source(&self) -> Option<(i64, i64)> {
+        let neutral = if self.op == 1 { 0 } else { 1 };
+        match (self.op, self.modes, self.params) {
+            (1 | 2, [m, 1, _], [p, n, _]) if n == neutral => Some((m, p)),
+            (1 | 2, [1, m, _], [n, p, _]) if n == neutral => Some((m, p)),
+            _ => None,
+        }
+    }
+}
+
+// the parameter written by an instruction
+fn writes(op: i64) -> Option<usize> {
+    match op {
+        1 | 2 | 7 | 8 => Some(2),
+        3 => Some(0),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub dead: usize,      // instructions found to have no effect
+    pub bypassed: usize,  // runs of dead instructions jumped over
+    pub threaded: usize,  // jumps retargeted past other jumps
+}
+
+// Finds the instructions reachable from address 0 following the
+// statically known control flow.
+fn discover(program: &[i64]) -> BTreeMap<usize, Instr> {
+    let mut instrs = BTreeMap::new();
+    let mut todo = vec![0];
+    while let Some(addr) = todo.pop() {
+        if instrs.contains_key(&addr) {
+            continue;
+        }
+        let i = match Instr::decode(program, addr) {
+            Some(i) => i,
+            None => continue,
+        };
+        instrs.insert(addr, i);
+        if i.op == 99 {
+            continue;
+        }
+        if i.taken() != Some(true) {
+            todo.push(i.next());
+        }
+        if let Some(t) = i.target() {
+            if i.taken() != Some(false) {
+                todo.push(t);
+            }
+        }
+    }
+    instrs
+}
+
+// Finds the cells that must not be rewritten: cells accessed as data
+// at fixed addresses, and cells claimed by overlapping instructions.
+fn protected(instrs: &BTreeMap<usize, Instr>) -> HashSet<usize> {
+    let mut cells = HashSet::new();
+    for i in instrs.values() {
+        for p in 0..i.len - 1 {
+            if i.modes[p] == 0 && i.params[p] >= 0 {
+                cells.insert(i.params[p] as usize);
+            }
+        }
+    }
+    let mut end = 0;
+    let mut last: Option<&Instr> = None;
+    for i in instrs.values() {
+        if i.addr < end {
+            cells.extend(i.cells());
+            cells.extend(last.unwrap().cells());
+        }
+        if i.next() > end {
+            end = i.next();
+            last = Some(i);
+        }
+    }
+    cells
+}
+
+// Whether the cells accessed by the program are all known: reads and
+// writes in relative mode can land anywhere, and so can the code
+// reached by computed jumps, which is never scanned.
+fn resolved(instrs: &BTreeMap<usize, Instr>) -> bool {
+    instrs.values().all(|i| {
+        let relative = (0..i.len - 1).any(|p| i.modes[p] == 2 || (i.modes[p] == 0 && i.params[p] < 0));
+        let computed_jump = matches!(i.op, 5 | 6) && i.target().is_none() && i.taken() != Some(false);
+        !relative && !computed_jump
+    })
+}
+
+// Programs accessing cells that cannot be resolved are left unchanged.
+pub fn optimize(program: &[i64]) -> (Vec<i64>, Report) {
+    let mut optimized = program.to_vec();
+    let mut report = Report::default();
+
+    let instrs = discover(program);
+    if !resolved(&instrs) {
+        return (optimized, report);
+    }
+    let protected = protected(&instrs);
+    let safe = |i: &Instr| !i.cells().any(|c| protected.contains(&c));
+
+    // bypass runs of dead instructions with a single jump
+    let dead = |i: &Instr| safe(i) && i.is_dead();
+    report.dead = instrs.values().filter(|i| dead(i)).count();
+    let mut runs = Vec::new();
+    let mut it = instrs.values().peekable();
+    while let Some(first) = it.next() {
+        if !dead(first) {
+            continue;
+        }
+        let mut end = first.next();
+        let mut count = 1;
+        while let Some(i) = it.peek().filter(|i| i.addr == end && dead(i)) {
+            end = i.next();
+            count += 1;
+            it.next();
+        }
+        if count > 1 && instrs.contains_key(&end) {
+            runs.push((first.addr, end));
+        }
+    }
+    for (start, end) in runs {
+        let jump = Instr { addr: start, op: 5, len: 3, modes: [1, 1, 0], params: [1, end as i64, 0] };
+        jump.encode(&mut optimized);
+        report.bypassed += 1;
+    }
+
+    // thread jumps through unconditional jumps and dead instructions
+    let instrs = discover(&optimized);
+    let mut retargets = Vec::new();
+    for i in instrs.values().filter(|i| safe(i)) {
+        let mut target = match i.target() {
+            Some(t) if i.taken() != Some(false) => t,
+            _ => continue,
+        };
+        let mut seen = HashSet::new();
+        while seen.insert(target) {
+            match instrs.get(&target).filter(|j| safe(j)) {
+                Some(j) if j.taken() == Some(true) && j.target().is_some() => target = j.target().unwrap(),
+                Some(j) if j.is_dead() => target = j.next(),
+                _ => break,
+            }
+        }
+        if Some(target) != i.target() && instrs.contains_key(&target) {
+            retargets.push(Instr { params: [i.params[0], target as i64, 0], ..*i });
+        }
+    }
+    for i in retargets {
+        i.encode(&mut optimized);
+        report.threaded += 1;
+    }
+
+    (optimized, report)
+}
+
+// Two machines, running the original and the optimized program side
+// by side. Panics as soon as their behaviour differs.
+pub struct SideBySide {
+    pub original: IntCode,
+    pub optimized: IntCode,
+}
+
+impl SideBySide {
+    pub fn new(program: &[i64]) -> SideBySide {
+        SideBySide {
+            original: IntCode::new(program),
+            optimized: IntCode::new(&optimize(program).0),
+        }
+    }
+}
+
+impl Machine for SideBySide {
+    fn push_input(&mut self, value: i64) {
+        self.original.input.push_back(value);
+        self.optimized.input.push_back(value);
+    }
+
+    fn run(&mut self) {
+        self.original.run();
+        self.optimized.run();
+        assert_eq!(self.original.output, self.optimized.output, "optimized program output differs");
+        assert_eq!(
+            self.original.is_halted(),
+            self.optimized.is_halted(),
+            "optimized program halting differs"
+        );
+    }
+
+    fn take_output(&mut self) -> Vec<i64> {
+        self.optimized.output.clear();
+        self.original.take_output()
+    }
+
+    fn read(&self, addr: usize) -> i64 {
+        self.original.read(addr)
+    }
+
+    fn is_halted(&self) -> bool {
+        self.original.is_halted()
+    }
+}
+
+// Runs the original and the optimized program with the same input,
+// returning their output if it matches.
+pub fn verify(original: &[i64], optimized: &[i64], input: &[i64]) -> Result<Vec<i64>, String> {
+    let mut c1 = IntCode::new(original);
+    let mut c2 = IntCode::new(optimized);
+    c1.input.extend(input);
+    c2.input.extend(input);
+    c1.run();
+    c2.run();
+    if c1.output != c2.output {
+        return Err(format!("output {:?}, expected {:?}", c2.output, c1.output));
+    }
+    if c1.is_halted() != c2.is_halted() {
+        return Err(format!("halted: {}, expected {}", c2.is_halted(), c1.is_halted()));
+    }
+    Ok(c1.output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::conformance::check_all;
+
+    #[test]
+    fn rewrites() {
+        let program = [
+            1101, 2, 3, 30,   // 0: mem[30] = 2 + 3
+            102, 1, 30, 31,   // 4: mem[31] = 1 * mem[30]
+            1001, 31, 0, 31,  // 8: mem[31] = mem[31] + 0
+            1105, 0, 99,      // 12: never taken
+            1106, 0, 18,      // 15: jump to the next instruction
+            1105, 1, 23,      // 18: jump to a jump
+            104, 0,           // 21: unreachable
+            1105, 1, 26,      // 23: jump to the next instruction
+            4, 31,            // 26: output mem[31]
+            99,               // 28
+        ];
+        let (optimized, report) = optimize(&program);
+        assert_eq!(report, Report { dead: 4, bypassed: 1, threaded: 2 });
+        assert_eq!(&optimized[0..8], &program[0..8]);
+        assert_eq!(&optimized[8..11], &[1105, 1, 26]);
+        assert_eq!(&optimized[18..21], &[1105, 1, 26]);
+        assert_eq!(verify(&program, &optimized, &[]), Ok(vec![5]));
+    }
+
+    #[test]
+    fn self_modifying() {
+        // the dead moves at 4 and 8 read their own code, and 4 is patched
+        // at runtime
+        let program = [1101, 2, 1, 5, 102, 1, 4, 4, 1001, 9, 0, 9, 104, 0, 99];
+        let (optimized, report) = optimize(&program);
+        assert_eq!(report, Report::default());
+        assert_eq!(verify(&program, &optimized, &[]), Ok(vec![0]));
+    }
+
+    #[test]
+    fn relative() {
+        // the write at 2 patches the constant at 7 through the relative base
+        let program = [109, 7, 21101, 40, 0, 0, 1101, 2, 3, 20, 4, 20, 99];
+        let (optimized, report) = optimize(&program);
+        assert_eq!(report, Report::default());
+        assert_eq!(optimized, program);
+        assert_eq!(verify(&program, &optimized, &[]), Ok(vec![43]));
+
+        // the output at 6 reads the code at 1 through the relative base
+        let program = [1101, 2, 3, 20, 109, 0, 204, 1, 99];
+        assert_eq!(optimize(&program).0, program);
+        assert_eq!(verify(&program, &optimize(&program).0, &[]), Ok(vec![2]));
+    }
+
+    #[test]
+    fn conformance() {
+        assert_eq!(check_all(|p| IntCode::new(&optimize(p).0)), vec![]);
+        assert_eq!(check_all(SideBySide::new), vec![]);
+    }
+}
@@ -0,0 +1,82 @@
+// A pool of machines running the same program. The program image is
+// shared, and machines returned to the pool are restored by copying
+// back only the memory pages they modified.
+use super::IntCode;
+use std::rc::Rc;
+
+pub struct Pool {
+    image: Rc<[i64]>,
+    machines: Vec<IntCode>,
+}
+
+impl Pool {
+    pub fn new(program: &[i64]) -> Pool {
+        Pool {
+            image: program.into(),
+            machines: Vec::new(),
+        }
+    }
+
+    // Returns a machine in its initial state.
+    pub fn get(&mut self) -> IntCode {
+        self.machines
+            .pop()
+            .unwrap_or_else(|| IntCode::from_image(&self.image))
+    }
+
+    // Gives a machine back to the pool, to be reused.
+    pub fn put(&mut self, mut machine: IntCode) {
+        assert!(
+            machine.image.as_ref().filter(|i| Rc::ptr_eq(i, &self.image)).is_some(),
+            "machine does not belong to this pool"
+        );
+        machine.restore();
+        self.machines.push(machine);
+    }
+
+    // Runs a short-lived machine with the given input, returning its output.
+    pub fn run(&mut self, input: &[i64]) -> Vec<i64> {
+        let mut c = self.get();
+        c.input.extend(input);
+        c.run();
+        let output = std::mem::take(&mut c.output);
+        self.put(c);
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::conformance::check_all;
+
+    #[test]
+    fn conformance() {
+        // machines that were used and restored must behave like new ones
+        let failures = check_all(|program| {
+            let mut pool = Pool::new(program);
+            pool.run(&[1, 2, 3]);
+            pool.get()
+        });
+        assert_eq!(failures, vec![]);
+    }
+
+    #[test]
+    fn restore() {
+        // self-modifying program that also writes beyond its image
+        let program = [3,100,1001,100,1,7,104,0,99];
+        let mut pool = Pool::new(&program);
+        assert_eq!(pool.run(&[5]), vec![6]);
+
+        let mut c = pool.get();
+        assert_eq!(c.memory, program);
+        c.poke(0, 104);
+        c.run();
+        assert_eq!(c.output, vec![100, 1]);
+        pool.put(c);
+
+        assert_eq!(pool.run(&[9]), vec![10]);
+        let c = pool.get();
+        assert_eq!(c.memory, program);
+    }
+}
@@ -0,0 +1,173 @@
+// A round-robin scheduler for networks of `IntCode` machines.
+//
+// Each machine in turn runs for a slice of instructions, then its
+// output is handed to a routing callback which returns the values to
+// deliver, as (destination, value) pairs. The machines always run in
+// the same order, so a network behaves the same way on every run.
+//
+// The network is deadlocked when every machine has halted or is
+// blocked on input with nothing to read. Machines that poll their
+// input (like the NICs of day 23) can be given an idle value to read
+// instead of blocking; they count as blocked when they read it without
+// producing any output.
+use super::{IntCode, Status};
+
+const DEFAULT_SLICE: usize = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Halted,   // every machine has halted
+    Deadlock, // no machine can make progress
+}
+
+pub struct Scheduler {
+    pub machines: Vec<IntCode>,
+    slice: usize,             // instructions per turn
+    idle_input: Option<i64>,  // read by machines with an empty input queue
+    status: Vec<Status>,      // as of the last turn of each machine
+    idle: Vec<bool>,          // whether the last turn made no progress
+}
+
+impl Scheduler {
+    pub fn new(machines: Vec<IntCode>) -> Scheduler {
+        let n = machines.len();
+        Scheduler {
+            machines,
+            slice: DEFAULT_SLICE,
+            idle_input: None,
+            status: vec![Status::Running; n],
+            idle: vec![false; n],
+        }
+    }
+
+    pub fn with_slice(mut self, slice: usize) -> Scheduler {
+        assert!(slice > 0, "empty time slice");
+        self.slice = slice;
+        self
+    }
+
+    pub fn with_idle_input(mut self, value: i64) -> Scheduler {
+        self.idle_input = Some(value);
+        self
+    }
+
+    // The status of each machine after its last turn.
+    pub fn status(&self) -> &[Status] {
+        &self.status
+    }
+
+    pub fn send(&mut self, to: usize, values: &[i64]) {
+        self.machines[to].input.extend(values);
+    }
+
+    fn is_blocked(&self, i: usize) -> bool {
+        match self.status[i] {
+            Status::Halted => true,
+            Status::WaitingInput => self.idle[i] && self.machines[i].input.is_empty(),
+            Status::Running => false,
+        }
+    }
+
+    // Gives one turn to each machine, returning how the network is
+    // stuck, if it is.
+    pub fn round<R>(&mut self, route: &mut R) -> Option<Outcome>
+    where
+        R: FnMut(usize, &mut IntCode) -> Vec<(usize, i64)>,
+    {
+        for i in 0..self.machines.len() {
+            let machine = &mut self.machines[i];
+            if self.status[i] == Status::Halted {
+                continue;
+            }
+            let starved = machine.input.is_empty() && self.status[i] == Status::WaitingInput;
+            if starved {
+                match self.idle_input {
+                    Some(v) => machine.input.push_back(v),
+                    None => {
+                        self.idle[i] = true;
+                        continue;
+                    }
+                }
+            }
+            self.status[i] = machine.run_for(self.slice);
+            self.idle[i] = starved && machine.output.is_empty();
+            if !machine.output.is_empty() {
+                for (to, v) in route(i, machine) {
+                    self.machines[to].input.push_back(v);
+                }
+            }
+        }
+
+        if self.status.iter().all(|s| *s == Status::Halted) {
+            Some(Outcome::Halted)
+        } else if (0..self.machines.len()).all(|i| self.is_blocked(i)) {
+            Some(Outcome::Deadlock)
+        } else {
+            None
+        }
+    }
+
+    // Runs the network until all the machines halt or it deadlocks.
+    pub fn run<R>(&mut self, mut route: R) -> Outcome
+    where
+        R: FnMut(usize, &mut IntCode) -> Vec<(usize, i64)>,
+    {
+        loop {
+            if let Some(outcome) = self.round(&mut route) {
+                return outcome;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // adds the input values two by two, until it reads 0
+    const ADDER: [i64; 17] = [3, 100, 1006, 100, 16, 3, 101, 1, 100, 101, 102, 4, 102, 1105, 1, 0, 99];
+
+    #[test]
+    fn pipeline() {
+        let machines = vec![IntCode::new(&ADDER), IntCode::new(&ADDER)];
+        let mut s = Scheduler::new(machines).with_slice(3);
+        s.send(0, &[1, 2, 3, 4, 0]);
+        let mut result = Vec::new();
+        let outcome = s.run(|from, c| {
+            if from == 0 {
+                c.output.drain(..).map(|v| (1, v)).collect()
+            } else {
+                result.append(&mut c.output);
+                Vec::new()
+            }
+        });
+        // the second machine is left waiting for more input
+        assert_eq!(outcome, Outcome::Deadlock);
+        assert_eq!(s.status(), &[Status::Halted, Status::WaitingInput]);
+        assert_eq!(result, vec![10]);
+
+        s.send(1, &[5, 6, 0]);
+        assert_eq!(s.run(|_, c| c.output.drain(..).map(|v| (0, v)).collect()), Outcome::Halted);
+        assert_eq!(s.machines[0].input, vec![11]);
+    }
+
+    #[test]
+    fn idle_input() {
+        // outputs the first value that is not -1, then halts
+        let program = [3, 20, 1008, 20, -1, 21, 1005, 21, 0, 4, 20, 99];
+        let machines = vec![IntCode::new(&program), IntCode::new(&program)];
+        let mut s = Scheduler::new(machines).with_idle_input(-1);
+        let mut routed = 0;
+        let mut route = |_, c: &mut IntCode| {
+            routed += c.output.len();
+            c.output.clear();
+            Vec::new()
+        };
+        assert_eq!(s.round(&mut route), None);
+        assert_eq!(s.round(&mut route), Some(Outcome::Deadlock));
+        s.send(1, &[7]);
+        assert_eq!(s.round(&mut route), Some(Outcome::Deadlock));
+        assert_eq!(s.status(), &[Status::WaitingInput, Status::Halted]);
+        assert_eq!(routed, 1);
+    }
+}
@@ -0,0 +1,690 @@
+// Symbolic execution of Intcode programs, to find the inputs that make
+// a program produce a given result without trying them all.
+//
+// Some memory cells and inputs are variables, each with a domain of
+// values. Values are tracked as linear expressions of the variables
+// through additions and multiplications by constants. A comparison or
+// a jump on a symbolic value forks the execution into two paths, each
+// recording the condition it assumes. The goal is then solved on each
+// path: all the variables but one are enumerated, and the last one is
+// computed from the equation.
+//
+// When a value becomes nonlinear (the product of two variables, or a
+// read through a symbolic address) and is needed, or when the program
+// uses symbolic values in ways that can't be followed (as a jump
+// target, an address to write to or an instruction), the solver falls
+// back to running the program on every combination of values.
+use super::{IntCode, Machine};
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+const MAX_PATHS: usize = 1000;
+const MAX_STEPS: usize = 10_000_000; // over all the paths
+
+// A linear expression: the sum of a constant and of variables with
+// their coefficients (never zero).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Linear {
+    pub constant: i64,
+    pub terms: BTreeMap<usize, i64>,
+}
+
+impl Linear {
+    fn constant(v: i64) -> Linear {
+        Linear { constant: v, terms: BTreeMap::new() }
+    }
+
+    fn variable(var: usize) -> Linear {
+        Linear { constant: 0, terms: std::iter::once((var, 1)).collect() }
+    }
+
+    pub fn as_constant(&self) -> Option<i64> {
+        if self.terms.is_empty() {
+            Some(self.constant)
+        } else {
+            None
+        }
+    }
+
+    fn add(&self, other: &Linear) -> Option<Linear> {
+        let mut sum = self.clone();
+        sum.constant = sum.constant.checked_add(other.constant)?;
+        for (var, k) in other.terms.iter() {
+            let c = sum.terms.get(var).unwrap_or(&0).checked_add(*k)?;
+            if c == 0 {
+                sum.terms.remove(var);
+            } else {
+                sum.terms.insert(*var, c);
+            }
+        }
+        Some(sum)
+    }
+
+    fn scale(&self, k: i64) -> Option<Linear> {
+        if k == 0 {
+            return Some(Linear::constant(0));
+        }
+        let mut terms = BTreeMap::new();
+        for (var, c) in self.terms.iter() {
+            terms.insert(*var, c.checked_mul(k)?);
+        }
+        Some(Linear { constant: self.constant.checked_mul(k)?, terms })
+    }
+
+    fn sub(&self, other: &Linear) -> Option<Linear> {
+        self.add(&other.scale(-1)?)
+    }
+
+    pub fn eval(&self, values: &[i64]) -> Option<i64> {
+        let mut v = self.constant as i128;
+        for (var, k) in self.terms.iter() {
+            v += *k as i128 * values[*var] as i128;
+        }
+        v.try_into().ok()
+    }
+
+    // The smallest and largest values over the domains of the variables.
+    fn bounds(&self, domains: &[RangeInclusive<i64>]) -> (i128, i128) {
+        let (mut lo, mut hi) = (self.constant as i128, self.constant as i128);
+        for (var, k) in self.terms.iter() {
+            let a = *k as i128 * *domains[*var].start() as i128;
+            let b = *k as i128 * *domains[*var].end() as i128;
+            lo += a.min(b);
+            hi += a.max(b);
+        }
+        (lo, hi)
+    }
+}
+
+impl Display for Linear {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut first = true;
+        for (var, k) in self.terms.iter() {
+            let sign = if *k < 0 { "-" } else if first { "" } else { "+" };
+            let space = if first { "" } else { " " };
+            match k.abs() {
+                1 => write!(f, "{}{}{}x{}", space, sign, space, var)?,
+                k => write!(f, "{}{}{}{}*x{}", space, sign, space, k, var)?,
+            }
+            first = false;
+        }
+        match self.constant {
+            c if first => write!(f, "{}", c),
+            0 => Ok(()),
+            c if c < 0 => write!(f, " - {}", -(c as i128)),
+            c => write!(f, " + {}", c),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Sym {
+    Linear(Linear),
+    Unknown, // not a linear expression
+}
+
+impl Sym {
+    fn constant(v: i64) -> Sym {
+        Sym::Linear(Linear::constant(v))
+    }
+
+    fn as_constant(&self) -> Option<i64> {
+        match self {
+            Sym::Linear(l) => l.as_constant(),
+            Sym::Unknown => None,
+        }
+    }
+
+    fn add(&self, other: &Sym) -> Sym {
+        match (self, other) {
+            (Sym::Linear(a), Sym::Linear(b)) => a.add(b).map_or(Sym::Unknown, Sym::Linear),
+            _ => Sym::Unknown,
+        }
+    }
+
+    fn mul(&self, other: &Sym) -> Sym {
+        match (self, other) {
+            (Sym::Linear(a), Sym::Linear(b)) => match (a.as_constant(), b.as_constant()) {
+                (Some(k), _) => b.scale(k).map_or(Sym::Unknown, Sym::Linear),
+                (_, Some(k)) => a.scale(k).map_or(Sym::Unknown, Sym::Linear),
+                _ => Sym::Unknown,
+            },
+            _ => Sym::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    Zero,
+    NonZero,
+    Negative,
+    NonNegative,
+}
+
+// A condition assumed by a path: `expr` is in the given relation to 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Constraint {
+    pub expr: Linear,
+    pub relation: Relation,
+}
+
+impl Constraint {
+    pub fn holds(&self, values: &[i64]) -> bool {
+        let v = match self.expr.eval(values) {
+            Some(v) => v,
+            None => return false,
+        };
+        match self.relation {
+            Relation::Zero => v == 0,
+            Relation::NonZero => v != 0,
+            Relation::Negative => v < 0,
+            Relation::NonNegative => v >= 0,
+        }
+    }
+
+    // false if the constraint can't hold for any values in the domains
+    fn feasible(&self, domains: &[RangeInclusive<i64>]) -> bool {
+        let (lo, hi) = self.expr.bounds(domains);
+        match self.relation {
+            Relation::Zero => lo <= 0 && 0 <= hi,
+            Relation::NonZero => lo != 0 || hi != 0,
+            Relation::Negative => lo < 0,
+            Relation::NonNegative => hi >= 0,
+        }
+    }
+}
+
+// A way through the program, with the conditions it assumes on the
+// variables and the state at the end.
+#[derive(Clone, Debug)]
+pub struct Path {
+    pub constraints: Vec<Constraint>,
+    pub memory: Vec<Sym>,
+    pub output: Vec<Sym>,
+    pub halted: bool, // false if the input ran out
+    ptr: usize,
+    base: i64,
+    input: usize,
+}
+
+impl Path {
+    pub fn read(&self, addr: usize) -> Sym {
+        self.memory.get(addr).cloned().unwrap_or_else(|| Sym::constant(0))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Goal {
+    Memory(usize, i64), // a cell holds a value when the program stops
+    Output(usize, i64), // the n-th output is a value
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Input {
+    Value(i64),
+    Var(usize),
+}
+
+// what happens to a path after an instruction
+enum Step {
+    Next,
+    Fork(Vec<Path>), // the other paths taken
+    End,
+}
+
+// where an instruction parameter points
+enum Operand {
+    Value(Sym),
+    Cell(usize),
+    Symbolic, // a cell at a symbolic address
+}
+
+pub struct Symbolic {
+    program: Rc<[i64]>,
+    domains: Vec<RangeInclusive<i64>>,
+    cells: Vec<(usize, usize)>, // symbolic cells and their variable
+    input: Vec<Input>,
+}
+
+impl Symbolic {
+    pub fn new(program: &[i64]) -> Symbolic {
+        Symbolic::from_image(&Rc::from(program))
+    }
+
+    // Shares the program image, as `IntCode::from_image` does.
+    pub fn from_image(image: &Rc<[i64]>) -> Symbolic {
+        Symbolic {
+            program: image.clone(),
+            domains: Vec::new(),
+            cells: Vec::new(),
+            input: Vec::new(),
+        }
+    }
+
+    // Makes a memory cell a new variable.
+    pub fn cell(mut self, addr: usize, domain: RangeInclusive<i64>) -> Symbolic {
+        self.cells.push((addr, self.domains.len()));
+        self.domains.push(domain);
+        self
+    }
+
+    // Adds a new variable to the input.
+    pub fn input(mut self, domain: RangeInclusive<i64>) -> Symbolic {
+        self.input.push(Input::Var(self.domains.len()));
+        self.domains.push(domain);
+        self
+    }
+
+    // Adds a known value to the input.
+    pub fn value(mut self, v: i64) -> Symbolic {
+        self.input.push(Input::Value(v));
+        self
+    }
+
+    // Finds all the paths through the program, or fails if the program
+    // uses symbolic values in a way that can't be followed.
+    pub fn explore(&self) -> Result<Vec<Path>, String> {
+        let mut memory: Vec<Sym> = self.program.iter().map(|v| Sym::constant(*v)).collect();
+        for (addr, var) in self.cells.iter() {
+            if *addr >= memory.len() {
+                memory.resize(addr + 1, Sym::constant(0));
+            }
+            memory[*addr] = Sym::Linear(Linear::variable(*var));
+        }
+        let start = Path {
+            constraints: Vec::new(),
+            memory,
+            output: Vec::new(),
+            halted: false,
+            ptr: 0,
+            base: 0,
+            input: 0,
+        };
+
+        let mut todo = vec![start];
+        let mut paths = Vec::new();
+        let mut steps = 0;
+        while let Some(mut path) = todo.pop() {
+            loop {
+                steps += 1;
+                if steps > MAX_STEPS {
+                    return Err("too many steps".to_string());
+                }
+                match self.step(&mut path)? {
+                    Step::Next => {}
+                    Step::Fork(others) => todo.extend(others),
+                    Step::End => break,
+                }
+                if todo.len() + paths.len() > MAX_PATHS {
+                    return Err("too many paths".to_string());
+                }
+            }
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    fn operand(&self, path: &Path, mode: i64, param: &Sym) -> Result<Operand, String> {
+        let addr = match mode {
+            0 => param.clone(),
+            1 => return Ok(Operand::Value(param.clone())),
+            2 => param.add(&Sym::constant(path.base)),
+            _ => return Err(format!("{}: invalid mode {}", path.ptr, mode)),
+        };
+        match addr.as_constant() {
+            Some(a) if a < 0 => Err(format!("{}: negative address", path.ptr)),
+            Some(a) => Ok(Operand::Cell(a as usize)),
+            None => Ok(Operand::Symbolic),
+        }
+    }
+
+    fn load(path: &Path, op: &Operand) -> Sym {
+        match op {
+            Operand::Value(v) => v.clone(),
+            Operand::Cell(addr) => path.read(*addr),
+            Operand::Symbolic => Sym::Unknown,
+        }
+    }
+
+    fn store(path: &mut Path, op: &Operand, v: Sym) -> Result<(), String> {
+        match op {
+            Operand::Cell(addr) => {
+                if *addr >= path.memory.len() {
+                    path.memory.resize(addr + 1, Sym::constant(0));
+                }
+                path.memory[*addr] = v;
+                Ok(())
+            }
+            Operand::Value(_) => Err(format!("{}: write to an immediate", path.ptr)),
+            Operand::Symbolic => Err(format!("{}: write to a symbolic address", path.ptr)),
+        }
+    }
+
+    // Adds a constraint to a copy of the path, unless it can't hold.
+    fn assume(&self, path: &Path, expr: Linear, relation: Relation) -> Option<Path> {
+        let c = Constraint { expr, relation };
+        if !c.feasible(&self.domains) {
+            return None;
+        }
+        let mut path = path.clone();
+        if c.expr.as_constant().is_none() {
+            path.constraints.push(c);
+        }
+        Some(path)
+    }
+
+    // Keeps the first of the possible paths, returning the others.
+    fn fork(path: &mut Path, mut paths: Vec<Path>) -> Step {
+        match paths.pop() {
+            Some(p) => {
+                *path = p;
+                Step::Fork(paths)
+            }
+            None => Step::End,
+        }
+    }
+
+    fn step(&self, path: &mut Path) -> Result<Step, String> {
+        let at = path.ptr;
+        let mut instr = match path.read(at).as_constant() {
+            Some(i) => i,
+            None => return Err(format!("{}: symbolic instruction", at)),
+        };
+        let op = instr % 100;
+        let len = match op {
+            1 | 2 | 7 | 8 => 4,
+            3 | 4 | 9 => 2,
+            5 | 6 => 3,
+            99 => 1,
+            _ => return Err(format!("{}: invalid opcode {}", at, op)),
+        };
+        instr /= 100;
+        let mut operands = Vec::new();
+        for p in 1..len {
+            operands.push(self.operand(path, instr % 10, &path.read(at + p))?);
+            instr /= 10;
+        }
+
+        let next = at + len;
+        match op {
+            1 | 2 => {
+                let a = Self::load(path, &operands[0]);
+                let b = Self::load(path, &operands[1]);
+                let v = if op == 1 { a.add(&b) } else { a.mul(&b) };
+                Self::store(path, &operands[2], v)?;
+            }
+            3 => {
+                let v = match self.input.get(path.input) {
+                    Some(Input::Value(v)) => Sym::constant(*v),
+                    Some(Input::Var(var)) => Sym::Linear(Linear::variable(*var)),
+                    None => return Ok(Step::End),
+                };
+                path.input += 1;
+                Self::store(path, &operands[0], v)?;
+            }
+            4 => {
+                let v = Self::load(path, &operands[0]);
+                path.output.push(v);
+            }
+            5 | 6 => {
+                let cond = Self::load(path, &operands[0]);
+                let target = match Self::load(path, &operands[1]).as_constant() {
+                    Some(t) if t >= 0 => t as usize,
+                    Some(_) => return Err(format!("{}: negative jump target", at)),
+                    None => return Err(format!("{}: symbolic jump target", at)),
+                };
+                let cond = match cond {
+                    Sym::Linear(l) => l,
+                    Sym::Unknown => return Err(format!("{}: jump on a nonlinear condition", at)),
+                };
+                if let Some(c) = cond.as_constant() {
+                    path.ptr = if (c != 0) == (op == 5) { target } else { next };
+                    return Ok(Step::Next);
+                }
+                let (jump, fall) = if op == 5 {
+                    (Relation::NonZero, Relation::Zero)
+                } else {
+                    (Relation::Zero, Relation::NonZero)
+                };
+                let mut paths = Vec::new();
+                for (relation, ptr) in [(jump, target), (fall, next)].iter() {
+                    if let Some(mut p) = self.assume(path, cond.clone(), *relation) {
+                        p.ptr = *ptr;
+                        paths.push(p);
+                    }
+                }
+                return Ok(Self::fork(path, paths));
+            }
+            7 | 8 => {
+                let a = Self::load(path, &operands[0]);
+                let b = Self::load(path, &operands[1]);
+                let diff = match (&a, &b) {
+                    (Sym::Linear(a), Sym::Linear(b)) => a.sub(b),
+                    _ => None,
+                };
+                let diff = match diff {
+                    Some(d) => d,
+                    None => {
+                        Self::store(path, &operands[2], Sym::Unknown)?;
+                        path.ptr = next;
+                        return Ok(Step::Next);
+                    }
+                };
+                let (yes, no) = if op == 7 {
+                    (Relation::Negative, Relation::NonNegative)
+                } else {
+                    (Relation::Zero, Relation::NonZero)
+                };
+                let mut paths = Vec::new();
+                for (relation, v) in [(yes, 1), (no, 0)].iter() {
+                    if let Some(mut p) = self.assume(path, diff.clone(), *relation) {
+                        Self::store(&mut p, &operands[2], Sym::constant(*v))?;
+                        p.ptr = next;
+                        paths.push(p);
+                    }
+                }
+                return Ok(Self::fork(path, paths));
+            }
+            9 => match Self::load(path, &operands[0]).as_constant() {
+                Some(v) => path.base += v,
+                None => return Err(format!("{}: symbolic relative base", at)),
+            },
+            _ => {
+                path.halted = true;
+                return Ok(Step::End);
+            }
+        }
+        path.ptr = next;
+        Ok(Step::Next)
+    }
+
+    // Finds values of the variables (in the order they were added) for
+    // which the program reaches the goal.
+    pub fn solve(&self, goal: Goal) -> Option<Vec<i64>> {
+        let paths = match self.explore() {
+            Ok(paths) => paths,
+            Err(_) => return self.enumerate(goal),
+        };
+        let mut equations = Vec::new();
+        for path in paths.iter() {
+            let v = match goal {
+                Goal::Memory(addr, v) if path.halted => (path.read(addr), v),
+                Goal::Output(n, v) if n < path.output.len() => (path.output[n].clone(), v),
+                _ => continue,
+            };
+            match v {
+                (Sym::Linear(e), v) => equations.push((e, v, &path.constraints)),
+                (Sym::Unknown, _) => return self.enumerate(goal),
+            }
+        }
+        // the same solution whatever the order of the paths
+        equations.iter().filter_map(|(e, v, constraints)| self.solve_linear(e, *v, constraints)).min()
+    }
+
+    // Solves `e = target` under the constraints of a path.
+    fn solve_linear(&self, e: &Linear, target: i64, constraints: &[Constraint]) -> Option<Vec<i64>> {
+        let pivot = e.terms.iter().next_back().map(|(var, k)| (*var, *k));
+        let used = |var: usize| {
+            e.terms.contains_key(&var) || constraints.iter().any(|c| c.expr.terms.contains_key(&var))
+        };
+
+        // enumerate the other variables that matter, fix the rest
+        let domains: Vec<_> = (0..self.domains.len())
+            .map(|var| match pivot {
+                Some((p, _)) if p == var => *self.domains[var].start()..=*self.domains[var].start(),
+                _ if !used(var) => *self.domains[var].start()..=*self.domains[var].start(),
+                _ => self.domains[var].clone(),
+            })
+            .collect();
+        for mut values in assignments(&domains) {
+            if let Some((p, k)) = pivot {
+                values[p] = 0;
+                // no solution with these values if the expression overflows
+                let rest = match e.eval(&values) {
+                    Some(rest) => rest as i128,
+                    None => continue,
+                };
+                let rhs = target as i128 - rest;
+                if rhs % k as i128 != 0 {
+                    continue;
+                }
+                match i64::try_from(rhs / k as i128) {
+                    Ok(v) if self.domains[p].contains(&v) => values[p] = v,
+                    _ => continue,
+                }
+            } else if e.constant != target {
+                return None;
+            }
+            if constraints.iter().all(|c| c.holds(&values)) {
+                return Some(values);
+            }
+        }
+        None
+    }
+
+    // Tries all the values of the variables, running the program on
+    // the same machine, restored each time.
+    fn enumerate(&self, goal: Goal) -> Option<Vec<i64>> {
+        let mut c = IntCode::from_image(&self.program);
+        assignments(&self.domains).find(|values| {
+            c.restore();
+            self.run(&mut c, values, goal)
+        })
+    }
+
+    // Runs the program with the given values of the variables.
+    pub fn check(&self, values: &[i64], goal: Goal) -> bool {
+        self.run(&mut IntCode::from_image(&self.program), values, goal)
+    }
+
+    fn run(&self, c: &mut IntCode, values: &[i64], goal: Goal) -> bool {
+        for (addr, var) in self.cells.iter() {
+            c.poke(*addr, values[*var]);
+        }
+        for i in self.input.iter() {
+            c.push_input(match i {
+                Input::Value(v) => *v,
+                Input::Var(var) => values[*var],
+            });
+        }
+        c.run();
+        match goal {
+            Goal::Memory(addr, v) => c.is_halted() && c.read(addr) == v,
+            Goal::Output(n, v) => c.output.get(n) == Some(&v),
+        }
+    }
+}
+
+// All the combinations of values in the domains, in lexicographic order.
+fn assignments(domains: &[RangeInclusive<i64>]) -> impl Iterator<Item = Vec<i64>> + '_ {
+    let first = if domains.iter().any(|d| d.is_empty()) {
+        None
+    } else {
+        Some(domains.iter().map(|d| *d.start()).collect())
+    };
+    std::iter::successors(first, move |prev: &Vec<i64>| {
+        let mut next = prev.clone();
+        for i in (0..next.len()).rev() {
+            if next[i] < *domains[i].end() {
+                next[i] += 1;
+                return Some(next);
+            }
+            next[i] = *domains[i].start();
+        }
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::compiler::compile;
+
+    #[test]
+    fn linear() {
+        let program = compile("fn main() { write(3 * read() + 2 * read() + 1); }").unwrap();
+        let s = Symbolic::new(&program).input(0..=10).input(0..=10);
+        let paths = s.explore().unwrap();
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].output.len(), 1);
+        match &paths[0].output[0] {
+            Sym::Linear(e) => assert_eq!(e.to_string(), "3*x0 + 2*x1 + 1"),
+            Sym::Unknown => panic!("nonlinear output"),
+        }
+        assert_eq!(s.solve(Goal::Output(0, 23)), Some(vec![2, 8]));
+        assert_eq!(s.solve(Goal::Output(0, 2)), None);
+    }
+
+    #[test]
+    fn branches() {
+        let program = compile(
+            "fn main() {
+                let k = read();
+                let x = read();
+                if x < 5 { write(2 * x); } else if x == 7 { write(0); } else { write(x + 100 * k); }
+            }",
+        )
+        .unwrap();
+        let s = Symbolic::new(&program).value(1).input(-10..=10);
+        assert_eq!(s.explore().unwrap().len(), 3);
+        assert_eq!(s.solve(Goal::Output(0, 106)), Some(vec![6]));
+        assert_eq!(s.solve(Goal::Output(0, -8)), Some(vec![-4]));
+        assert_eq!(s.solve(Goal::Output(0, 0)), Some(vec![0]));
+        assert_eq!(s.solve(Goal::Output(0, 107)), None);
+        assert_eq!(s.solve(Goal::Output(0, 12)), None);
+    }
+
+    #[test]
+    fn cells() {
+        // mem[0] = 2 * mem[13] - mem[14]
+        let program = [1002,13,2,15, 102,-1,14,16, 1,15,16,0, 99, 0,0,0,0];
+        let s = Symbolic::new(&program).cell(13, 0..=99).cell(14, 0..=99);
+        let solution = s.solve(Goal::Memory(0, 150)).unwrap();
+        assert_eq!(solution, vec![75, 0]);
+        assert!(s.check(&solution, Goal::Memory(0, 150)));
+
+        // mem[0] = -2^62 * mem[13] + mem[14], overflowing for the first
+        // values of mem[13]
+        let program = [1002,13,-(1 << 62),15, 1,15,14,0, 99, 0,0,0,0, 0,0,0];
+        let s = Symbolic::new(&program).cell(13, -3..=1).cell(14, 0..=10);
+        assert_eq!(s.solve(Goal::Memory(0, (1 << 62) + 5)), Some(vec![-1, 5]));
+    }
+
+    #[test]
+    fn nonlinear() {
+        // the product of two inputs
+        let program = compile("fn main() { write(read() * read()); }").unwrap();
+        let s = Symbolic::new(&program).input(1..=5).input(1..=5);
+        assert_eq!(s.explore().unwrap()[0].output, vec![Sym::Unknown]);
+        assert_eq!(s.solve(Goal::Output(0, 12)), Some(vec![3, 4]));
+
+        // symbolic addresses, as in day 2
+        let program = [1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50];
+        let s = Symbolic::new(&program).cell(1, 9..=11).cell(2, 9..=11);
+        assert_eq!(s.solve(Goal::Memory(0, 3500)), Some(vec![9, 10]));
+    }
+}
@@ -1,3 +1,8 @@
+pub mod direction;
 pub mod graph;
 pub mod grid;
+pub mod image;
 pub mod intcode;
+pub mod log;
+pub mod netpbm;
+pub mod ocr;
@@ -0,0 +1,26 @@
+// Diagnostic output for the solutions (maps, intermediate results).
+// It is silent unless the environment variable AOC_LOG is set. Renders
+// are saved only in the directory named by AOC_EXPORT, if set.
+pub fn enabled() -> bool {
+    std::env::var_os("AOC_LOG").is_some()
+}
+
+#[macro_export]
+macro_rules! log {
+    ($($arg:tt)*) => {
+        if $crate::log::enabled() {
+            println!($($arg)*);
+        }
+    };
+}
+
+// Saves a render (an image, for example) as a file in the AOC_EXPORT
+// directory, if set. Failures are reported but not fatal.
+pub fn export(name: &str, content: &dyn std::fmt::Display) {
+    if let Some(dir) = std::env::var_os("AOC_EXPORT") {
+        let path = std::path::Path::new(&dir).join(name);
+        if let Err(e) = std::fs::write(&path, content.to_string()) {
+            eprintln!("cannot export {}: {}", path.display(), e);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+// Export of grids to the plain (ASCII) Netpbm formats: PBM for black
+// and white, PGM for grey levels and PPM for colours. Each symbol can be
+// given a colour; the other symbols are drawn with the ink colour and
+// the unset cells with the background colour. Every cell becomes a
+// square of `scale` x `scale` pixels.
+//
+//     let pbm = Netpbm::new(&grid, Format::Pbm).color('.', WHITE).to_string();
+use crate::grid::{Grid, Location};
+use std::fmt::Display;
+
+pub type Rgb = [u8; 3];
+
+pub const BLACK: Rgb = [0, 0, 0];
+pub const WHITE: Rgb = [255, 255, 255];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Pbm, // P1
+    Pgm, // P2
+    Ppm, // P3
+}
+
+pub struct Netpbm<'a, T> {
+    grid: &'a Grid<T>,
+    format: Format,
+    scale: usize,
+    palette: Vec<(T, Rgb)>,
+    ink: Rgb,
+    background: Rgb,
+}
+
+impl<'a, T: PartialEq> Netpbm<'a, T> {
+    pub fn new(grid: &'a Grid<T>, format: Format) -> Netpbm<'a, T> {
+        Netpbm {
+            grid,
+            format,
+            scale: 1,
+            palette: Vec::new(),
+            ink: BLACK,
+            background: WHITE,
+        }
+    }
+
+    pub fn with_scale(mut self, scale: usize) -> Self {
+        assert!(scale > 0, "empty pixels");
+        self.scale = scale;
+        self
+    }
+
+    pub fn color(mut self, symbol: T, color: Rgb) -> Self {
+        self.palette.push((symbol, color));
+        self
+    }
+
+    // The colour of the symbols not in the palette.
+    pub fn with_ink(mut self, color: Rgb) -> Self {
+        self.ink = color;
+        self
+    }
+
+    // The colour of the unset cells.
+    pub fn with_background(mut self, color: Rgb) -> Self {
+        self.background = color;
+        self
+    }
+
+    fn pixel(&self, l: &Location) -> Rgb {
+        match self.grid.get(l) {
+            None => self.background,
+            Some(c) => self.palette.iter().find(|(s, _)| s == c).map_or(self.ink, |(_, color)| *color),
+        }
+    }
+}
+
+// perceived brightness, 0 to 255
+fn luma([r, g, b]: Rgb) -> u8 {
+    ((299 * r as u32 + 587 * g as u32 + 114 * b as u32) / 1000) as u8
+}
+
+impl<'a, T: PartialEq> Display for Netpbm<'a, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (x0, y0) = (self.grid.x_min(), self.grid.y_min());
+        let (width, height) = if self.grid.is_empty() {
+            (0, 0)
+        } else {
+            ((self.grid.x_max() - x0 + 1) as usize, (self.grid.y_max() - y0 + 1) as usize)
+        };
+        match self.format {
+            Format::Pbm => writeln!(f, "P1\n{} {}", width * self.scale, height * self.scale)?,
+            Format::Pgm => writeln!(f, "P2\n{} {}\n255", width * self.scale, height * self.scale)?,
+            Format::Ppm => writeln!(f, "P3\n{} {}\n255", width * self.scale, height * self.scale)?,
+        }
+        for y in 0..height * self.scale {
+            let row: Vec<String> = (0..width * self.scale)
+                .map(|x| {
+                    let l = Location { x: x0 + (x / self.scale) as i64, y: y0 + (y / self.scale) as i64 };
+                    let color = self.pixel(&l);
+                    match self.format {
+                        // 1 is black
+                        Format::Pbm => ((luma(color) < 128) as u8).to_string(),
+                        Format::Pgm => luma(color).to_string(),
+                        Format::Ppm => format!("{} {} {}", color[0], color[1], color[2]),
+                    }
+                })
+                .collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats() {
+        let g = Grid::<char>::create_from("#.\n.o\n");
+        assert_eq!(Netpbm::new(&g, Format::Pbm).color('.', WHITE).to_string(), "P1\n2 2\n1 0\n0 1\n");
+        assert_eq!(
+            Netpbm::new(&g, Format::Pgm).color('o', [255, 0, 0]).with_ink(WHITE).to_string(),
+            "P2\n2 2\n255\n255 255\n255 76\n"
+        );
+        assert_eq!(
+            Netpbm::new(&g, Format::Ppm).color('#', [1, 2, 3]).with_ink(BLACK).to_string(),
+            "P3\n2 2\n255\n1 2 3 0 0 0\n0 0 0 0 0 0\n"
+        );
+    }
+
+    #[test]
+    fn scale() {
+        let mut g = Grid::new();
+        g.insert(Location { x: -1, y: 3 }, 1);
+        g.insert(Location { x: 0, y: 4 }, 1);
+        let pbm = Netpbm::new(&g, Format::Pbm).with_scale(2).with_background(BLACK).color(1, WHITE);
+        assert_eq!(pbm.to_string(), "P1\n4 4\n0 0 1 1\n0 0 1 1\n1 1 0 0\n1 1 0 0\n");
+        assert_eq!(Netpbm::new(&Grid::<u8>::new(), Format::Pbm).to_string(), "P1\n0 0\n");
+    }
+}
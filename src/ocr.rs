@@ -0,0 +1,157 @@
+// Recognition of the block letters drawn by the puzzles, in the 4x6 font
+// (4 pixels wide, 6 high) and in the larger 6x10 font. The letters sit
+// in cells of fixed width from the first column with ink (5 pixels and
+// 8 pixels), as some letters touch the next one.
+use crate::grid::{Grid, Location};
+use crate::image::SpaceImage;
+use std::collections::HashSet;
+
+const FONT_4X6: &[(char, [&str; 6])] = &[
+    ('A', [".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', ["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', [".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', ["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', ["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', [".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', ["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', [".###", "..#.", "..#.", "..#.", "..#.", ".###"]),
+    ('J', ["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', ["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', ["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', [".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', ["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', ["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', [".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', ["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', ["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', ["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+const FONT_6X10: &[(char, [&str; 10])] = &[
+    ('A', ["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', ["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', [".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', ["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', [".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', ["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', ["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', ["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', ["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', ["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', ["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', ["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', ["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', ["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+];
+
+// Reads the letters drawn with the cells satisfying `ink`. Unknown
+// letters are reported with their bitmap.
+pub fn read<T>(grid: &Grid<T>, ink: impl Fn(&T) -> bool) -> Result<String, String> {
+    let inked: HashSet<Location> = grid.cells_matching(ink).collect();
+    if inked.is_empty() {
+        return Ok(String::new());
+    }
+    let (x0, x1) = (inked.iter().map(|l| l.x).min().unwrap(), inked.iter().map(|l| l.x).max().unwrap());
+    let (y0, y1) = (inked.iter().map(|l| l.y).min().unwrap(), inked.iter().map(|l| l.y).max().unwrap());
+    let is_ink = |x, y| inked.contains(&Location { x, y });
+
+    let height = (y1 - y0 + 1) as usize;
+    let (font, width): (Vec<(char, Vec<String>)>, i64) = match height {
+        6 => (FONT_4X6.iter().map(|(c, rows)| (*c, trim(rows))).collect(), 5),
+        10 => (FONT_6X10.iter().map(|(c, rows)| (*c, trim(rows))).collect(), 8),
+        _ => return Err(format!("no font for letters {} pixels high", height)),
+    };
+
+    // one letter per cell, blank cells are skipped
+    let mut text = String::new();
+    for start in (x0..=x1).step_by(width as usize) {
+        let rows: Vec<String> = (y0..=y1)
+            .map(|y| (start..start + width).map(|x| if is_ink(x, y) { '#' } else { '.' }).collect())
+            .collect();
+        let bitmap = trim(&rows.iter().map(|r| r.as_str()).collect::<Vec<_>>());
+        if bitmap[0].is_empty() {
+            continue;
+        }
+        match font.iter().find(|(_, rows)| *rows == bitmap) {
+            Some((c, _)) => text.push(*c),
+            None => return Err(format!("unknown letter at x = {}:\n{}", start, bitmap.join("\n"))),
+        }
+    }
+    Ok(text)
+}
+
+// Reads the letters of the white pixels of an image.
+pub fn read_image(img: &SpaceImage) -> Result<String, String> {
+    read(&img.composed(), |&p| p == 1)
+}
+
+// removes the blank columns around a letter
+fn trim(rows: &[&str]) -> Vec<String> {
+    let blank = |i: usize| rows.iter().all(|r| r.as_bytes()[i] == b'.');
+    let width = rows[0].len();
+    let start = (0..width).find(|&i| !blank(i)).unwrap_or(0);
+    let end = (0..width).rev().find(|&i| !blank(i)).map_or(0, |i| i + 1);
+    rows.iter().map(|r| r[start..end].to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_font() {
+        let rows = [
+            " ██  ███  █  █ █   █",
+            "█  █ █  █ █  █ █   █",
+            "█  █ ███  ████  █ █",
+            "████ █  █ █  █   █",
+            "█  █ █  █ █  █   █",
+            "█  █ ███  █  █   █",
+        ];
+        let g: Grid = Grid::create_from(&rows.join("\n"));
+        assert_eq!(read(&g, |&c| c == '█'), Ok("ABHY".to_string()));
+
+        // a 'Y' touches the next letter
+        let rows = [
+            "█   █ ██ ",
+            "█   ██  █",
+            " █ █ █  █",
+            "  █  ████",
+            "  █  █  █",
+            "  █  █  █",
+        ];
+        let g: Grid = Grid::create_from(&rows.join("\n"));
+        assert_eq!(read(&g, |&c| c == '█'), Ok("YA".to_string()));
+
+        let img = SpaceImage::new(5, 6, b"100101001010010100101001001100222222222222222222222222222222");
+        assert_eq!(read_image(&img), Ok("U".to_string()));
+    }
+
+    #[test]
+    fn large_font() {
+        let rows = [
+            "#....#..######",
+            "#....#.......#",
+            ".#..#........#",
+            ".#..#.......#.",
+            "..##.......#..",
+            "..##......#...",
+            ".#..#....#....",
+            ".#..#...#.....",
+            "#....#..#.....",
+            "#....#..######",
+        ];
+        let g: Grid = Grid::create_from(&rows.join("\n"));
+        assert_eq!(read(&g, |&c| c == '#'), Ok("XZ".to_string()));
+    }
+
+    #[test]
+    fn unknown() {
+        let g: Grid = Grid::create_from("#..#\n.##.\n#..#\n.##.\n#..#\n.##.");
+        assert_eq!(read(&g, |&c| c == '#'), Err("unknown letter at x = 0:\n#..#\n.##.\n#..#\n.##.\n#..#\n.##.".into()));
+        let g: Grid = Grid::create_from("#\n#");
+        assert_eq!(read(&g, |&c| c == '#'), Err("no font for letters 2 pixels high".into()));
+        assert_eq!(read(&Grid::<char>::new(), |&c| c == '#'), Ok(String::new()));
+    }
+}